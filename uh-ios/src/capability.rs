@@ -149,7 +149,7 @@ mod tests {
     #[test]
     fn test_capability_check() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         // VM should have Execute capability after creation
         assert!(CapabilityManager::check_capability(&state, vmid, Capability::Execute));
@@ -171,7 +171,7 @@ mod tests {
     #[test]
     fn test_capability_revoke() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         assert!(CapabilityManager::check_capability(&state, vmid, Capability::Execute));
         
@@ -183,7 +183,7 @@ mod tests {
     #[test]
     fn test_require_capability() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         // Should succeed with capability
         assert!(CapabilityManager::require_capability(&state, vmid, Capability::Execute).is_ok());
@@ -197,7 +197,7 @@ mod tests {
     #[test]
     fn test_get_capabilities() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         let caps = CapabilityManager::get_capabilities(&state, vmid).unwrap();
         
@@ -209,8 +209,8 @@ mod tests {
     #[test]
     fn test_capability_transfer() {
         let mut state = SystemState::new();
-        let vm1 = VMManager::create_vm(&mut state).unwrap();
-        let vm2 = VMManager::create_vm(&mut state).unwrap();
+        let vm1 = VMManager::create_vm(&mut state, 1).unwrap();
+        let vm2 = VMManager::create_vm(&mut state, 1).unwrap();
         
         // Remove Execute from vm2
         CapabilityManager::revoke_capability(&mut state, vm2, Capability::Execute).unwrap();
@@ -230,8 +230,8 @@ mod tests {
     #[test]
     fn test_capability_move() {
         let mut state = SystemState::new();
-        let vm1 = VMManager::create_vm(&mut state).unwrap();
-        let vm2 = VMManager::create_vm(&mut state).unwrap();
+        let vm1 = VMManager::create_vm(&mut state, 1).unwrap();
+        let vm2 = VMManager::create_vm(&mut state, 1).unwrap();
         
         // Remove Halt from vm2
         CapabilityManager::revoke_capability(&mut state, vm2, Capability::Halt).unwrap();
@@ -0,0 +1,333 @@
+//! ELF64 guest coredump generation
+//!
+//! Mirrors cloud-hypervisor's `coredump` module (`CpuElf64Writable`,
+//! `NT_PRSTATUS` notes, per-CPU `X86_64UserRegs`) but for the AArch64
+//! guests modeled here: [`CoreDump::dump_core`] writes an ELF64 `ET_CORE`
+//! image with one `NT_PRSTATUS` note per vCPU encoding its
+//! [`crate::types::CPUState`], plus one `PT_LOAD` program header per
+//! mapped memory region. This module has no `HVFContext` to read guest
+//! memory through, so `PT_LOAD` segments describe region extents only
+//! (`p_filesz` is always 0) rather than embedding guest memory contents.
+
+use std::io::Write;
+
+use crate::types::{CPUState, SystemState, VMState, VMID};
+use crate::{Error, Result};
+
+const EI_NIDENT: usize = 16;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+const ET_CORE: u16 = 4;
+const EM_AARCH64: u16 = 183;
+
+const EHDR_SIZE: u64 = 64;
+const PHDR_SIZE: u64 = 56;
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_R: u32 = 4;
+const PF_W: u32 = 2;
+const PF_X: u32 = 1;
+
+const NT_PRSTATUS: u32 = 1;
+
+/// Note name, null-padded to the standard 8-byte `COREDUMP_NAME_SIZE`
+const COREDUMP_NAME_SIZE: usize = 8;
+/// Note name including its null terminator, before padding
+const NOTE_NAME: &[u8] = b"CORE\0";
+
+/// AArch64 `user_pt_regs`: x0-x30, sp, pc, pstate (34 registers)
+const PRSTATUS_DESC_SIZE: usize = 34 * 8;
+
+/// Generates ELF64 core images from trapped or halted VM state
+pub struct CoreDump;
+
+impl CoreDump {
+    /// Write an ELF64 core image for `vmid` to `writer`
+    ///
+    /// Formal precondition: VM exists in Trapped or Halted state
+    /// Formal postcondition: `writer` holds a well-formed `ET_CORE` ELF64
+    /// image: one `PT_NOTE` segment with an `NT_PRSTATUS` note per vCPU,
+    /// followed by one `PT_LOAD` header per mapped memory region. A Halted
+    /// VM carries no CPU state, so its `PT_NOTE` segment is empty.
+    pub fn dump_core(state: &SystemState, vmid: VMID, mut writer: impl Write) -> Result<()> {
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+
+        let cpu_states: &[CPUState] = match vm_state {
+            VMState::Trapped(_, cpus) => cpus,
+            VMState::Halted => &[],
+            _ => return Err(Error::InvalidVMState(vmid)),
+        };
+
+        let regions = state.memory.get(&vmid).cloned().unwrap_or_default();
+        let notes = Self::encode_notes(cpu_states);
+
+        let phnum = 1 + regions.len();
+        let phoff = EHDR_SIZE;
+        let note_offset = phoff + (phnum as u64) * PHDR_SIZE;
+
+        writer
+            .write_all(&Self::encode_ehdr(phoff, phnum as u16))
+            .map_err(Self::io_err)?;
+
+        writer
+            .write_all(&Self::encode_phdr(
+                PT_NOTE,
+                0,
+                note_offset,
+                0,
+                0,
+                notes.len() as u64,
+                notes.len() as u64,
+                4,
+            ))
+            .map_err(Self::io_err)?;
+
+        for region in &regions {
+            writer
+                .write_all(&Self::encode_phdr(
+                    PT_LOAD,
+                    PF_R | PF_W | PF_X,
+                    0,
+                    region.gpa.0,
+                    region.gpa.0,
+                    0,
+                    region.size,
+                    0x1000,
+                ))
+                .map_err(Self::io_err)?;
+        }
+
+        writer.write_all(&notes).map_err(Self::io_err)?;
+
+        Ok(())
+    }
+
+    fn encode_ehdr(phoff: u64, phnum: u16) -> Vec<u8> {
+        let mut ident = [0u8; EI_NIDENT];
+        ident[0..4].copy_from_slice(&[0x7f, b'E', b'L', b'F']);
+        ident[4] = ELFCLASS64;
+        ident[5] = ELFDATA2LSB;
+        ident[6] = EV_CURRENT;
+        // ident[7] (OSABI), ident[8] (ABI version), ident[9..16] (padding) stay 0
+
+        let mut bytes = Vec::with_capacity(EHDR_SIZE as usize);
+        bytes.extend_from_slice(&ident);
+        bytes.extend_from_slice(&ET_CORE.to_le_bytes());
+        bytes.extend_from_slice(&EM_AARCH64.to_le_bytes());
+        bytes.extend_from_slice(&(EV_CURRENT as u32).to_le_bytes()); // e_version
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+        bytes.extend_from_slice(&phoff.to_le_bytes()); // e_phoff
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // e_shoff
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+        bytes.extend_from_slice(&(EHDR_SIZE as u16).to_le_bytes()); // e_ehsize
+        bytes.extend_from_slice(&(PHDR_SIZE as u16).to_le_bytes()); // e_phentsize
+        bytes.extend_from_slice(&phnum.to_le_bytes()); // e_phnum
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // e_shentsize
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // e_shnum
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // e_shstrndx
+
+        debug_assert_eq!(bytes.len() as u64, EHDR_SIZE);
+        bytes
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn encode_phdr(
+        p_type: u32,
+        p_flags: u32,
+        p_offset: u64,
+        p_vaddr: u64,
+        p_paddr: u64,
+        p_filesz: u64,
+        p_memsz: u64,
+        p_align: u64,
+    ) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PHDR_SIZE as usize);
+        bytes.extend_from_slice(&p_type.to_le_bytes());
+        bytes.extend_from_slice(&p_flags.to_le_bytes());
+        bytes.extend_from_slice(&p_offset.to_le_bytes());
+        bytes.extend_from_slice(&p_vaddr.to_le_bytes());
+        bytes.extend_from_slice(&p_paddr.to_le_bytes());
+        bytes.extend_from_slice(&p_filesz.to_le_bytes());
+        bytes.extend_from_slice(&p_memsz.to_le_bytes());
+        bytes.extend_from_slice(&p_align.to_le_bytes());
+
+        debug_assert_eq!(bytes.len() as u64, PHDR_SIZE);
+        bytes
+    }
+
+    /// Encode one `NT_PRSTATUS` note per vCPU
+    fn encode_notes(cpu_states: &[CPUState]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for cpu in cpu_states {
+            let desc = Self::encode_prstatus(cpu);
+
+            buf.extend_from_slice(&(NOTE_NAME.len() as u32).to_le_bytes()); // namesz
+            buf.extend_from_slice(&(desc.len() as u32).to_le_bytes()); // descsz
+            buf.extend_from_slice(&NT_PRSTATUS.to_le_bytes()); // type
+
+            let mut name = [0u8; COREDUMP_NAME_SIZE];
+            name[..NOTE_NAME.len()].copy_from_slice(NOTE_NAME);
+            buf.extend_from_slice(&name);
+
+            buf.extend_from_slice(&desc);
+        }
+        buf
+    }
+
+    /// Encode a `CPUState` as an AArch64 `user_pt_regs` blob: x0-x30, sp,
+    /// pc, pstate, little-endian
+    fn encode_prstatus(cpu: &CPUState) -> [u8; PRSTATUS_DESC_SIZE] {
+        let mut bytes = [0u8; PRSTATUS_DESC_SIZE];
+        let mut offset = 0;
+        for gpr in cpu.gpr.iter() {
+            bytes[offset..offset + 8].copy_from_slice(&gpr.to_le_bytes());
+            offset += 8;
+        }
+        bytes[offset..offset + 8].copy_from_slice(&cpu.sp.to_le_bytes());
+        offset += 8;
+        bytes[offset..offset + 8].copy_from_slice(&cpu.pc.to_le_bytes());
+        offset += 8;
+        bytes[offset..offset + 8].copy_from_slice(&cpu.pstate.to_le_bytes());
+
+        bytes
+    }
+
+    fn io_err(e: std::io::Error) -> Error {
+        Error::ExitError(format!("coredump write failed: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::VMManager;
+
+    #[test]
+    fn test_dump_core_rejects_runnable_vm() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+
+        let mut buf = Vec::new();
+        assert!(CoreDump::dump_core(&state, vmid, &mut buf).is_err());
+    }
+
+    #[test]
+    fn test_dump_core_trapped_vm_has_valid_elf_header() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        VMManager::trap_vm(
+            &mut state,
+            vmid,
+            0,
+            crate::types::ExitReason::WFI,
+            CPUState::default(),
+        )
+        .unwrap();
+
+        let mut buf = Vec::new();
+        CoreDump::dump_core(&state, vmid, &mut buf).unwrap();
+
+        assert_eq!(&buf[0..4], &[0x7f, b'E', b'L', b'F']);
+        assert_eq!(buf[4], ELFCLASS64);
+        assert_eq!(u16::from_le_bytes([buf[16], buf[17]]), ET_CORE);
+        assert_eq!(u16::from_le_bytes([buf[18], buf[19]]), EM_AARCH64);
+        // One PT_NOTE header, no mapped memory regions
+        assert_eq!(u16::from_le_bytes([buf[56], buf[57]]), 1);
+    }
+
+    #[test]
+    fn test_dump_core_encodes_one_note_per_vcpu() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 2).unwrap();
+        VMManager::initialize_vm(
+            &mut state,
+            vmid,
+            vec![CPUState::default(), CPUState::default()],
+        )
+        .unwrap();
+        VMManager::trap_vm(
+            &mut state,
+            vmid,
+            0,
+            crate::types::ExitReason::WFI,
+            CPUState {
+                pc: 0x1000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let mut buf = Vec::new();
+        CoreDump::dump_core(&state, vmid, &mut buf).unwrap();
+
+        // header + 1 PT_NOTE phdr = 120 bytes before note data begins
+        let note_start = (EHDR_SIZE + PHDR_SIZE) as usize;
+        let namesz = u32::from_le_bytes(buf[note_start..note_start + 4].try_into().unwrap());
+        let descsz =
+            u32::from_le_bytes(buf[note_start + 4..note_start + 8].try_into().unwrap());
+        let note_type =
+            u32::from_le_bytes(buf[note_start + 8..note_start + 12].try_into().unwrap());
+
+        assert_eq!(namesz, NOTE_NAME.len() as u32);
+        assert_eq!(descsz, PRSTATUS_DESC_SIZE as u32);
+        assert_eq!(note_type, NT_PRSTATUS);
+
+        let note_len = 12 + COREDUMP_NAME_SIZE + PRSTATUS_DESC_SIZE;
+        assert_eq!(buf.len(), note_start + note_len * 2);
+    }
+
+    #[test]
+    fn test_dump_core_halted_vm_has_no_notes() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::halt_vm(&mut state, vmid).unwrap();
+
+        let mut buf = Vec::new();
+        CoreDump::dump_core(&state, vmid, &mut buf).unwrap();
+
+        // header + 1 PT_NOTE phdr, no note bytes follow
+        assert_eq!(buf.len(), (EHDR_SIZE + PHDR_SIZE) as usize);
+    }
+
+    #[test]
+    fn test_dump_core_emits_pt_load_per_memory_region() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        state.memory.insert(
+            vmid,
+            vec![crate::memory::MemoryRegion {
+                gpa: crate::types::GPA(0x8000_0000),
+                size: 0x10_0000,
+            }],
+        );
+        VMManager::trap_vm(
+            &mut state,
+            vmid,
+            0,
+            crate::types::ExitReason::WFI,
+            CPUState::default(),
+        )
+        .unwrap();
+
+        let mut buf = Vec::new();
+        CoreDump::dump_core(&state, vmid, &mut buf).unwrap();
+
+        let phnum = u16::from_le_bytes([buf[56], buf[57]]);
+        assert_eq!(phnum, 2); // PT_NOTE + one PT_LOAD
+
+        let load_phdr_start = (EHDR_SIZE + PHDR_SIZE) as usize;
+        let p_type = u32::from_le_bytes(buf[load_phdr_start..load_phdr_start + 4].try_into().unwrap());
+        assert_eq!(p_type, PT_LOAD);
+        let p_vaddr = u64::from_le_bytes(
+            buf[load_phdr_start + 16..load_phdr_start + 24]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(p_vaddr, 0x8000_0000);
+    }
+}
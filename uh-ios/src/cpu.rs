@@ -0,0 +1,273 @@
+//! Per-vCPU thread run loop driving the exit queue
+//!
+//! Runs each initialized VM's vCPU on its own OS thread calling into the
+//! [`crate::hvf::HVF`] run loop, instead of requiring callers to manually
+//! `trap_vm`/`process_next_exit`. On a real VM exit the thread pushes the
+//! exit onto `state.exits` (via `VMManager::trap_vm`) and blocks until
+//! either the dispatcher resumes the VM or the thread is asked to stop.
+//!
+//! Because every vCPU thread serializes through the same
+//! `Mutex<SystemState>`, `state.exits` remains the single point where
+//! exits are ordered: `ExitHandler::handle_exit` still only ever sees one
+//! exit at a time, so deterministic exit handling holds regardless of how
+//! many vCPU threads are running concurrently.
+//!
+//! Each vCPU thread installs a [`crate::sandbox`] filter for
+//! [`crate::sandbox::ThreadCategory::Vcpu`] before its first
+//! `HVF::run_vcpu` call, so a compromised guest that escapes HVF is still
+//! confined to that thread's allow-listed syscalls.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::hvf::{HVFContext, HVF};
+use crate::sandbox::{self, SeccompAction, ThreadCategory};
+use crate::types::{SystemState, VMState, VMID};
+use crate::vm::VMManager;
+use crate::{Error, Result};
+
+/// How often a blocked vCPU thread re-checks `stop`/runnable state
+///
+/// This is a fallback interval only: `notify_resumed`/`request_stop`
+/// normally wake the thread immediately via the condvar.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// System state shared across vCPU threads
+pub type SharedState = Arc<Mutex<SystemState>>;
+
+/// Per-vCPU control block letting the dispatcher stop or resume a
+/// running vCPU thread asynchronously
+struct VcpuControl {
+    /// Set to ask the vCPU thread to exit its run loop
+    stop: Arc<AtomicBool>,
+    /// Woken whenever `stop` changes or the VM is resumed, so a guest
+    /// spinning in `WFI` or a tight loop can always be kicked between
+    /// exits rather than needing to be preempted mid-instruction
+    kick: Arc<Condvar>,
+    handle: JoinHandle<()>,
+}
+
+/// Drives per-vCPU run loops on dedicated OS threads
+///
+/// Keyed by `(VMID, vcpu index)` rather than just `VMID` so an SMP guest
+/// gets one thread per vCPU, each trapping through
+/// [`VMManager::trap_vm`] with its own index.
+#[derive(Default)]
+pub struct CpuManager {
+    vcpus: HashMap<(VMID, u32), VcpuControl>,
+}
+
+impl CpuManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        CpuManager {
+            vcpus: HashMap::new(),
+        }
+    }
+
+    /// Spawn a thread driving vCPU index `vcpu_index` of `vmid`
+    ///
+    /// Formal precondition: `vmid` is `Runnable` in `state` with at least
+    /// `vcpu_index + 1` per-vCPU CPU states.
+    pub fn spawn_vcpu(
+        &mut self,
+        state: SharedState,
+        vmid: VMID,
+        vcpu_index: u32,
+        ctx: HVFContext,
+        vcpu: u64,
+    ) -> Result<()> {
+        {
+            let guard = state.lock().unwrap();
+            match VMManager::get_vm_state(&guard, vmid)? {
+                VMState::Runnable(cpus) if (vcpu_index as usize) < cpus.len() => {}
+                _ => return Err(Error::InvalidVMState(vmid)),
+            }
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let kick = Arc::new(Condvar::new());
+
+        let thread_state = Arc::clone(&state);
+        let thread_stop = Arc::clone(&stop);
+        let thread_kick = Arc::clone(&kick);
+
+        let handle = std::thread::spawn(move || {
+            Self::run_loop(
+                thread_state,
+                vmid,
+                vcpu_index,
+                ctx,
+                vcpu,
+                thread_stop,
+                thread_kick,
+            );
+        });
+
+        self.vcpus.insert(
+            (vmid, vcpu_index),
+            VcpuControl {
+                stop,
+                kick,
+                handle,
+            },
+        );
+        Ok(())
+    }
+
+    fn run_loop(
+        state: SharedState,
+        vmid: VMID,
+        vcpu_index: u32,
+        ctx: HVFContext,
+        vcpu: u64,
+        stop: Arc<AtomicBool>,
+        kick: Arc<Condvar>,
+    ) {
+        // Sandbox this thread before it makes its first HVF::run_vcpu
+        // call; a filter that fails to install is treated the same as a
+        // run_vcpu/get_cpu_state failure below rather than running the
+        // vCPU unsandboxed.
+        let Ok(_filter) = sandbox::apply_filter(ThreadCategory::Vcpu, SeccompAction::Trap) else {
+            let _ = HVF::destroy_vcpu(vcpu);
+            let _ = ctx;
+            return;
+        };
+
+        let pause_lock = Mutex::new(());
+
+        while !stop.load(Ordering::SeqCst) {
+            let Ok(exit_reason) = HVF::run_vcpu(vcpu) else {
+                break;
+            };
+            let Ok(cpu_state) = HVF::get_cpu_state(vcpu) else {
+                break;
+            };
+
+            {
+                let mut guard = state.lock().unwrap();
+                if VMManager::trap_vm(&mut guard, vmid, vcpu_index, exit_reason, cpu_state).is_err() {
+                    break;
+                }
+            }
+
+            let mut guard = pause_lock.lock().unwrap();
+            while !stop.load(Ordering::SeqCst) && !Self::is_runnable(&state, vmid) {
+                guard = kick.wait_timeout(guard, POLL_INTERVAL).unwrap().0;
+            }
+        }
+
+        let _ = HVF::destroy_vcpu(vcpu);
+        let _ = ctx;
+    }
+
+    fn is_runnable(state: &SharedState, vmid: VMID) -> bool {
+        state
+            .lock()
+            .unwrap()
+            .vms
+            .get(&vmid)
+            .map(|s| matches!(s, VMState::Runnable(_)))
+            .unwrap_or(true)
+    }
+
+    /// Ask the vCPU thread for `(vmid, vcpu_index)` to stop after its
+    /// current exit
+    ///
+    /// Delivered asynchronously via the stop flag plus a condvar kick, so
+    /// a guest parked in `WFI` is woken rather than left blocked forever.
+    pub fn request_stop(&self, vmid: VMID, vcpu_index: u32) -> Result<()> {
+        let ctl = self
+            .vcpus
+            .get(&(vmid, vcpu_index))
+            .ok_or(Error::VMNotFound(vmid))?;
+        ctl.stop.store(true, Ordering::SeqCst);
+        ctl.kick.notify_all();
+        Ok(())
+    }
+
+    /// Wake the vCPU thread for `(vmid, vcpu_index)` without stopping it,
+    /// e.g. after the dispatcher applies an `ExitAction` that resumes the
+    /// VM
+    pub fn notify_resumed(&self, vmid: VMID, vcpu_index: u32) -> Result<()> {
+        let ctl = self
+            .vcpus
+            .get(&(vmid, vcpu_index))
+            .ok_or(Error::VMNotFound(vmid))?;
+        ctl.kick.notify_all();
+        Ok(())
+    }
+
+    /// Join the vCPU thread for `(vmid, vcpu_index)`, blocking until it
+    /// exits
+    ///
+    /// Formal precondition: `request_stop` was called, or the thread
+    /// already exited on its own.
+    pub fn join(&mut self, vmid: VMID, vcpu_index: u32) -> Result<()> {
+        let ctl = self
+            .vcpus
+            .remove(&(vmid, vcpu_index))
+            .ok_or(Error::VMNotFound(vmid))?;
+        ctl.handle
+            .join()
+            .map_err(|_| Error::ExitError(format!("vCPU thread for {vmid} panicked")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CPUState;
+
+    fn runnable_vm() -> (SharedState, VMID, HVFContext) {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        let ctx = HVF::create_vm(vmid).unwrap();
+        (Arc::new(Mutex::new(state)), vmid, ctx)
+    }
+
+    #[test]
+    fn test_spawn_requires_runnable_vm() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        let ctx = HVF::create_vm(vmid).unwrap();
+        let shared = Arc::new(Mutex::new(state));
+
+        let mut manager = CpuManager::new();
+        assert!(manager.spawn_vcpu(shared, vmid, 0, ctx, 0).is_err());
+    }
+
+    #[test]
+    fn test_spawn_stop_and_join() {
+        let (state, vmid, ctx) = runnable_vm();
+        let mut manager = CpuManager::new();
+
+        manager.spawn_vcpu(Arc::clone(&state), vmid, 0, ctx, 0).unwrap();
+        manager.request_stop(vmid, 0).unwrap();
+        manager.join(vmid, 0).unwrap();
+
+        // Join removed the control block; a second join fails cleanly
+        assert!(manager.join(vmid, 0).is_err());
+    }
+
+    #[test]
+    fn test_run_loop_enqueues_trap() {
+        let (state, vmid, ctx) = runnable_vm();
+        let mut manager = CpuManager::new();
+
+        manager.spawn_vcpu(Arc::clone(&state), vmid, 0, ctx, 0).unwrap();
+
+        // HVF::run_vcpu is a stub that always reports WFI, so the vCPU
+        // thread should trap at least once before we stop it.
+        std::thread::sleep(Duration::from_millis(20));
+        manager.request_stop(vmid, 0).unwrap();
+        manager.join(vmid, 0).unwrap();
+
+        let guard = state.lock().unwrap();
+        assert!(matches!(guard.vms.get(&vmid), Some(VMState::Trapped(_, _))));
+    }
+}
@@ -0,0 +1,404 @@
+//! GDB Remote Serial Protocol debug stub
+//!
+//! Exposes an RSP server over TCP for a VM currently in
+//! `VMState::Trapped`, gated by `Capability::Debug`. Implements the core
+//! packet loop: `g`/`G` to read/write the full `CPUState` register file,
+//! `m`/`M` to read/write guest memory through the HVF mapping layer,
+//! `c`/`s` to continue/single-step, and `Z0`/`z0` software breakpoints.
+//! This lets a developer attach lldb/gdb to a paused guest, matching the
+//! `gdbstub` integration cloud-hypervisor added to its VM layer.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::debugger::Debugger;
+use crate::exit::ExitHandler;
+use crate::hvf::{HVFContext, HVF};
+use crate::types::{Capability, CPUState, ExitReason, SystemState, VMState, VMID};
+use crate::vm::VMManager;
+use crate::{Error, Result};
+
+/// AArch64 `brk #0` trap instruction, little-endian encoded
+const BRK_INSTRUCTION: [u8; 4] = [0x00, 0x00, 0x20, 0xd4];
+
+/// A software breakpoint: the original instruction bytes overwritten with
+/// [`BRK_INSTRUCTION`], restored when the breakpoint is removed
+#[derive(Debug, Clone, Copy)]
+struct Breakpoint {
+    original: [u8; 4],
+}
+
+/// GDB Remote Serial Protocol debug stub for a single VM
+pub struct GdbStub {
+    vmid: VMID,
+    ctx: HVFContext,
+    breakpoints: HashMap<u64, Breakpoint>,
+}
+
+impl GdbStub {
+    /// Attach a debug stub to `vmid`
+    ///
+    /// Formal precondition: `vmid` possesses `Capability::Debug`.
+    pub fn new(state: &SystemState, vmid: VMID, ctx: HVFContext) -> Result<Self> {
+        if !state.has_capability(vmid, Capability::Debug) {
+            return Err(Error::CapabilityError(
+                "Debug capability required".to_string(),
+            ));
+        }
+
+        Ok(GdbStub {
+            vmid,
+            ctx,
+            breakpoints: HashMap::new(),
+        })
+    }
+
+    /// Listen on `addr` and serve RSP requests for this VM until the
+    /// client disconnects
+    pub fn serve(&mut self, state: &mut SystemState, addr: impl ToSocketAddrs) -> Result<()> {
+        let listener =
+            TcpListener::bind(addr).map_err(|e| Error::ExitError(format!("gdbstub bind: {e}")))?;
+        let (stream, _) = listener
+            .accept()
+            .map_err(|e| Error::ExitError(format!("gdbstub accept: {e}")))?;
+
+        self.serve_connection(state, stream)
+    }
+
+    /// Run the RSP packet loop over an already-accepted connection
+    pub fn serve_connection(&mut self, state: &mut SystemState, mut stream: TcpStream) -> Result<()> {
+        loop {
+            let Some(packet) = Self::read_packet(&mut stream)? else {
+                return Ok(());
+            };
+
+            Self::send_ack(&mut stream)?;
+
+            let reply = self.handle_packet(state, &packet)?;
+            Self::write_packet(&mut stream, &reply)?;
+        }
+    }
+
+    /// CPU state of vCPU 0
+    ///
+    /// The RSP protocol has no notion of multiple register files per
+    /// connection, so this stub always targets vCPU 0 of the VM; debugging
+    /// an individual vCPU of an SMP guest would require one `GdbStub` per
+    /// vCPU index.
+    fn trapped_cpu_state(state: &SystemState, vmid: VMID) -> Result<CPUState> {
+        match VMManager::get_vm_state(state, vmid)? {
+            VMState::Trapped(_, cpus) => cpus.first().cloned().ok_or(Error::InvalidVMState(vmid)),
+            _ => Err(Error::InvalidVMState(vmid)),
+        }
+    }
+
+    fn handle_packet(&mut self, state: &mut SystemState, packet: &str) -> Result<String> {
+        if let Some(args) = packet.strip_prefix("Z0,") {
+            return self.set_breakpoint(args);
+        }
+        if let Some(args) = packet.strip_prefix("z0,") {
+            return self.remove_breakpoint(args);
+        }
+
+        match packet.as_bytes().first() {
+            Some(b'g') => Ok(Self::encode_registers(&Self::trapped_cpu_state(
+                state, self.vmid,
+            )?)),
+            Some(b'G') => {
+                let mut cpu = Self::trapped_cpu_state(state, self.vmid)?;
+                Self::decode_registers(&packet[1..], &mut cpu)?;
+                Debugger::write_registers(state, self.vmid, 0, cpu)?;
+                Ok("OK".to_string())
+            }
+            Some(b'm') => self.read_memory(&packet[1..]),
+            Some(b'M') => self.write_memory(&packet[1..]),
+            Some(b'c') | Some(b's') => self.resume_and_report(state),
+            _ => Ok(String::new()),
+        }
+    }
+
+    /// Drive exactly one pending exit and translate it into an RSP
+    /// stop-reply. Used for both `c` (continue) and `s` (single-step):
+    /// single-step is modeled by advancing the exit queue by exactly one
+    /// entry rather than running the guest freely.
+    fn resume_and_report(&mut self, state: &mut SystemState) -> Result<String> {
+        let reason = match VMManager::get_vm_state(state, self.vmid)? {
+            VMState::Trapped(reason, _) => reason.clone(),
+            _ => return Err(Error::InvalidVMState(self.vmid)),
+        };
+
+        let Some((vmid, vcpu, action)) = ExitHandler::process_next_exit(state)? else {
+            return Ok("W00".to_string());
+        };
+
+        ExitHandler::apply_exit_action(state, vmid, vcpu, action)?;
+
+        Ok(Self::stop_reply(&reason))
+    }
+
+    fn stop_reply(_reason: &ExitReason) -> String {
+        "S05".to_string()
+    }
+
+    fn set_breakpoint(&mut self, args: &str) -> Result<String> {
+        let addr = Self::parse_addr(args)?;
+
+        let original_vec = HVF::read_guest_memory(self.ctx, addr, 4)
+            .map_err(|e| Error::ExitError(format!("breakpoint read failed: {e}")))?;
+        let mut original = [0u8; 4];
+        original.copy_from_slice(&original_vec);
+
+        HVF::write_guest_memory(self.ctx, addr, &BRK_INSTRUCTION)?;
+        self.breakpoints.insert(addr, Breakpoint { original });
+
+        Ok("OK".to_string())
+    }
+
+    fn remove_breakpoint(&mut self, args: &str) -> Result<String> {
+        let addr = Self::parse_addr(args)?;
+
+        if let Some(bp) = self.breakpoints.remove(&addr) {
+            HVF::write_guest_memory(self.ctx, addr, &bp.original)?;
+        }
+
+        Ok("OK".to_string())
+    }
+
+    fn read_memory(&self, args: &str) -> Result<String> {
+        let (addr, len) = Self::parse_addr_len(args)?;
+        let bytes = HVF::read_guest_memory(self.ctx, addr, len)?;
+        Ok(hex_encode(&bytes))
+    }
+
+    fn write_memory(&self, args: &str) -> Result<String> {
+        let (header, data) = args
+            .split_once(':')
+            .ok_or_else(|| Error::ExitError("malformed M packet".to_string()))?;
+        let (addr, len) = Self::parse_addr_len(header)?;
+        let bytes = hex_decode(data)?;
+        if bytes.len() != len {
+            return Err(Error::ExitError("M packet length mismatch".to_string()));
+        }
+
+        HVF::write_guest_memory(self.ctx, addr, &bytes)?;
+        Ok("OK".to_string())
+    }
+
+    fn parse_addr(s: &str) -> Result<u64> {
+        let addr_str = s.split(',').next().unwrap_or("");
+        u64::from_str_radix(addr_str, 16)
+            .map_err(|e| Error::ExitError(format!("malformed address: {e}")))
+    }
+
+    fn parse_addr_len(s: &str) -> Result<(u64, usize)> {
+        let (addr_str, len_str) = s
+            .split_once(',')
+            .ok_or_else(|| Error::ExitError("malformed addr,length".to_string()))?;
+        let addr = u64::from_str_radix(addr_str, 16)
+            .map_err(|e| Error::ExitError(format!("malformed address: {e}")))?;
+        let len = usize::from_str_radix(len_str, 16)
+            .map_err(|e| Error::ExitError(format!("malformed length: {e}")))?;
+        Ok((addr, len))
+    }
+
+    /// Encode `g` register dump: x0-x30, sp, pc, pstate, little-endian hex
+    fn encode_registers(cpu: &CPUState) -> String {
+        let mut bytes = Vec::with_capacity((31 + 3) * 8);
+        for gpr in cpu.gpr.iter() {
+            bytes.extend_from_slice(&gpr.to_le_bytes());
+        }
+        bytes.extend_from_slice(&cpu.sp.to_le_bytes());
+        bytes.extend_from_slice(&cpu.pc.to_le_bytes());
+        bytes.extend_from_slice(&cpu.pstate.to_le_bytes());
+        hex_encode(&bytes)
+    }
+
+    /// Decode a `G` register blob back into `cpu`
+    fn decode_registers(hex: &str, cpu: &mut CPUState) -> Result<()> {
+        let bytes = hex_decode(hex)?;
+        if bytes.len() != (31 + 3) * 8 {
+            return Err(Error::ExitError("G packet length mismatch".to_string()));
+        }
+
+        for (i, gpr) in cpu.gpr.iter_mut().enumerate() {
+            *gpr = u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap());
+        }
+        cpu.sp = u64::from_le_bytes(bytes[31 * 8..32 * 8].try_into().unwrap());
+        cpu.pc = u64::from_le_bytes(bytes[32 * 8..33 * 8].try_into().unwrap());
+        cpu.pstate = u64::from_le_bytes(bytes[33 * 8..34 * 8].try_into().unwrap());
+
+        Ok(())
+    }
+
+    fn read_packet(stream: &mut TcpStream) -> Result<Option<String>> {
+        let mut byte = [0u8; 1];
+        loop {
+            match stream.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) if byte[0] == b'$' => break,
+                Ok(_) => continue,
+                Err(e) => return Err(Error::ExitError(format!("gdbstub read: {e}"))),
+            }
+        }
+
+        let mut body = Vec::new();
+        loop {
+            stream
+                .read_exact(&mut byte)
+                .map_err(|e| Error::ExitError(format!("gdbstub read: {e}")))?;
+            if byte[0] == b'#' {
+                break;
+            }
+            body.push(byte[0]);
+        }
+
+        let mut checksum = [0u8; 2];
+        stream
+            .read_exact(&mut checksum)
+            .map_err(|e| Error::ExitError(format!("gdbstub read: {e}")))?;
+
+        String::from_utf8(body)
+            .map(Some)
+            .map_err(|e| Error::ExitError(format!("gdbstub packet not utf8: {e}")))
+    }
+
+    fn send_ack(stream: &mut TcpStream) -> Result<()> {
+        stream
+            .write_all(b"+")
+            .map_err(|e| Error::ExitError(format!("gdbstub ack: {e}")))
+    }
+
+    fn write_packet(stream: &mut TcpStream, payload: &str) -> Result<()> {
+        let checksum: u8 = payload.bytes().fold(0u8, |acc, b| acc.wrapping_add(b));
+        let framed = format!("${payload}#{checksum:02x}");
+        stream
+            .write_all(framed.as_bytes())
+            .map_err(|e| Error::ExitError(format!("gdbstub write: {e}")))
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hex_decode(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(Error::ExitError("odd-length hex string".to_string()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|e| Error::ExitError(format!("malformed hex byte: {e}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SystemState;
+
+    fn debug_vm() -> (SystemState, VMID, HVFContext) {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(&mut state, vmid, Capability::Debug)
+            .unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        VMManager::trap_vm(&mut state, vmid, 0, ExitReason::WFI, CPUState::default()).unwrap();
+
+        let ctx = HVF::create_vm(vmid).unwrap();
+        (state, vmid, ctx)
+    }
+
+    #[test]
+    fn test_new_requires_debug_capability() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        let ctx = HVF::create_vm(vmid).unwrap();
+
+        assert!(GdbStub::new(&state, vmid, ctx).is_err());
+    }
+
+    #[test]
+    fn test_register_round_trip() {
+        let mut cpu = CPUState {
+            pc: 0x4000,
+            sp: 0x8000,
+            ..Default::default()
+        };
+        cpu.gpr[0] = 0x42;
+
+        let encoded = GdbStub::encode_registers(&cpu);
+
+        let mut decoded = CPUState::default();
+        GdbStub::decode_registers(&encoded, &mut decoded).unwrap();
+
+        assert_eq!(decoded.pc, 0x4000);
+        assert_eq!(decoded.sp, 0x8000);
+        assert_eq!(decoded.gpr[0], 0x42);
+    }
+
+    #[test]
+    fn test_g_packet_writes_registers_without_resuming() {
+        let (mut state, vmid, ctx) = debug_vm();
+        let mut stub = GdbStub::new(&state, vmid, ctx).unwrap();
+
+        let mut cpu = CPUState {
+            pc: 0x4000,
+            ..Default::default()
+        };
+        cpu.gpr[0] = 0x42;
+        let packet = format!("G{}", GdbStub::encode_registers(&cpu));
+
+        let reply = stub.handle_packet(&mut state, &packet).unwrap();
+        assert_eq!(reply, "OK");
+
+        match VMManager::get_vm_state(&state, vmid).unwrap() {
+            VMState::Trapped(_, cpus) => assert_eq!(cpus[0].pc, 0x4000),
+            other => panic!("expected VM to remain Trapped, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_read_write_memory_packets() {
+        let (state, vmid, ctx) = debug_vm();
+        let stub = GdbStub::new(&state, vmid, ctx).unwrap();
+
+        let reply = stub.read_memory("1000,4").unwrap();
+        assert_eq!(reply.len(), 8); // 4 bytes hex-encoded
+
+        let reply = stub.write_memory("1000,4:deadbeef").unwrap();
+        assert_eq!(reply, "OK");
+    }
+
+    #[test]
+    fn test_set_and_remove_breakpoint() {
+        let (state, vmid, ctx) = debug_vm();
+        let mut stub = GdbStub::new(&state, vmid, ctx).unwrap();
+
+        assert_eq!(stub.set_breakpoint("1000,4").unwrap(), "OK");
+        assert!(stub.breakpoints.contains_key(&0x1000));
+
+        assert_eq!(stub.remove_breakpoint("1000,4").unwrap(), "OK");
+        assert!(!stub.breakpoints.contains_key(&0x1000));
+    }
+
+    #[test]
+    fn test_resume_and_report_stop_reply() {
+        let (mut state, vmid, ctx) = debug_vm();
+        let mut stub = GdbStub::new(&state, vmid, ctx).unwrap();
+
+        let reply = stub.resume_and_report(&mut state).unwrap();
+        assert_eq!(reply, "S05");
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = vec![0xde, 0xad, 0xbe, 0xef];
+        let hex = hex_encode(&bytes);
+        assert_eq!(hex, "deadbeef");
+        assert_eq!(hex_decode(&hex).unwrap(), bytes);
+    }
+}
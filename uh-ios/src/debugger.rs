@@ -0,0 +1,242 @@
+//! Interactive vCPU debugging over the `VMManager` state machine
+//!
+//! Layered beneath transports like [`crate::debug::GdbStub`]: `Debugger`
+//! reads and writes a single vCPU's register file, manages a per-VM
+//! breakpoint address set, and single-steps a vCPU by exactly one
+//! instruction. Mirrors cloud-hypervisor's `Debuggable` trait
+//! (`vmm/src/gdb.rs`), which exposes the same read/write/step primitives
+//! to a remote debugger session.
+
+use crate::hvf::HVF;
+use crate::types::{Capability, CPUState, ExitReason, SystemState, VMState, VMID};
+use crate::{Error, Result};
+
+/// Interactive debugging operations over a trapped or debugging VM
+pub struct Debugger;
+
+impl Debugger {
+    /// Read the register file of vCPU `vcpu` of `vmid`
+    ///
+    /// Formal precondition: VM exists in Trapped or Debugging state,
+    /// possesses Debug capability, `vcpu` is a valid vCPU index
+    pub fn read_registers(state: &SystemState, vmid: VMID, vcpu: u32) -> Result<CPUState> {
+        Self::require_debuggable(state, vmid)?;
+
+        Self::cpu_states(state, vmid)?
+            .get(vcpu as usize)
+            .cloned()
+            .ok_or(Error::InvalidVMState(vmid))
+    }
+
+    /// Overwrite the register file of vCPU `vcpu` of `vmid`
+    ///
+    /// Formal precondition: VM exists in Trapped or Debugging state,
+    /// possesses Debug capability, `vcpu` is a valid vCPU index
+    /// Formal postcondition: `vcpu`'s CPU state is `cpu_state`; the VM's
+    /// lifecycle state (Trapped/Debugging) is otherwise unchanged
+    pub fn write_registers(
+        state: &mut SystemState,
+        vmid: VMID,
+        vcpu: u32,
+        cpu_state: CPUState,
+    ) -> Result<()> {
+        Self::require_debuggable(state, vmid)?;
+
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+        let rebuilt = match vm_state {
+            VMState::Trapped(reason, cpus) => {
+                let mut cpus = cpus.clone();
+                let slot = cpus.get_mut(vcpu as usize).ok_or(Error::InvalidVMState(vmid))?;
+                *slot = cpu_state;
+                VMState::Trapped(reason.clone(), cpus)
+            }
+            VMState::Debugging(cpus) => {
+                let mut cpus = cpus.clone();
+                let slot = cpus.get_mut(vcpu as usize).ok_or(Error::InvalidVMState(vmid))?;
+                *slot = cpu_state;
+                VMState::Debugging(cpus)
+            }
+            _ => return Err(Error::InvalidVMState(vmid)),
+        };
+
+        state.vms.insert(vmid, rebuilt);
+        Ok(())
+    }
+
+    /// Register a software breakpoint at `guest_addr` for `vmid`
+    ///
+    /// Formal precondition: VM exists, possesses Debug capability
+    /// Formal postcondition: the next [`crate::vm::VMManager::trap_vm`]
+    /// whose vCPU's PC equals `guest_addr` records
+    /// `ExitReason::Breakpoint` instead of the reason HVF reported
+    pub fn set_breakpoint(state: &mut SystemState, vmid: VMID, guest_addr: u64) -> Result<()> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+        if !state.has_capability(vmid, Capability::Debug) {
+            return Err(Error::CapabilityError(
+                "Debug capability required".to_string(),
+            ));
+        }
+
+        state.breakpoints.entry(vmid).or_default().insert(guest_addr);
+        Ok(())
+    }
+
+    /// Remove a previously registered breakpoint
+    ///
+    /// Formal precondition: VM exists, possesses Debug capability
+    pub fn remove_breakpoint(state: &mut SystemState, vmid: VMID, guest_addr: u64) -> Result<()> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+        if !state.has_capability(vmid, Capability::Debug) {
+            return Err(Error::CapabilityError(
+                "Debug capability required".to_string(),
+            ));
+        }
+
+        if let Some(bps) = state.breakpoints.get_mut(&vmid) {
+            bps.remove(&guest_addr);
+        }
+        Ok(())
+    }
+
+    /// Single-step vCPU `vcpu` of `vmid` by exactly one instruction
+    ///
+    /// Formal precondition: VM exists in Trapped or Debugging state,
+    /// possesses Debug capability, `vcpu` is a valid vCPU index
+    /// Formal postcondition: `vcpu`'s CPU state is advanced by one
+    /// instruction via [`HVF::run_vcpu_single_step`] and the VM is in
+    /// Debugging state; the reported exit reason is `Breakpoint` if the
+    /// new PC matches a registered breakpoint, otherwise what HVF reported
+    pub fn step(state: &mut SystemState, vmid: VMID, vcpu: u32, hvf_vcpu: u64) -> Result<ExitReason> {
+        Self::require_debuggable(state, vmid)?;
+
+        let mut exit_reason = HVF::run_vcpu_single_step(hvf_vcpu)?;
+        let cpu_state = HVF::get_cpu_state(hvf_vcpu)?;
+
+        if state
+            .breakpoints
+            .get(&vmid)
+            .is_some_and(|bps| bps.contains(&cpu_state.pc))
+        {
+            exit_reason = ExitReason::Breakpoint;
+        }
+
+        let mut cpu_states = Self::cpu_states(state, vmid)?.clone();
+        let slot = cpu_states
+            .get_mut(vcpu as usize)
+            .ok_or(Error::InvalidVMState(vmid))?;
+        *slot = cpu_state;
+
+        state.vms.insert(vmid, VMState::Debugging(cpu_states));
+
+        Ok(exit_reason)
+    }
+
+    fn cpu_states(state: &SystemState, vmid: VMID) -> Result<&Vec<CPUState>> {
+        match state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))? {
+            VMState::Trapped(_, cpus) | VMState::Debugging(cpus) => Ok(cpus),
+            _ => Err(Error::InvalidVMState(vmid)),
+        }
+    }
+
+    fn require_debuggable(state: &SystemState, vmid: VMID) -> Result<()> {
+        if !state.has_capability(vmid, Capability::Debug) {
+            return Err(Error::CapabilityError(
+                "Debug capability required".to_string(),
+            ));
+        }
+        match state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))? {
+            VMState::Trapped(_, _) | VMState::Debugging(_) => Ok(()),
+            _ => Err(Error::InvalidVMState(vmid)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::VMManager;
+
+    fn trapped_vm() -> (SystemState, VMID) {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(&mut state, vmid, Capability::Debug)
+            .unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        VMManager::trap_vm(&mut state, vmid, 0, ExitReason::WFI, CPUState::default()).unwrap();
+        (state, vmid)
+    }
+
+    #[test]
+    fn test_read_write_registers_round_trip() {
+        let (mut state, vmid) = trapped_vm();
+
+        let cpu_state = CPUState {
+            pc: 0x1000,
+            ..Default::default()
+        };
+        Debugger::write_registers(&mut state, vmid, 0, cpu_state.clone()).unwrap();
+
+        let read_back = Debugger::read_registers(&state, vmid, 0).unwrap();
+        assert_eq!(read_back.pc, 0x1000);
+        assert!(matches!(state.vms.get(&vmid), Some(VMState::Trapped(_, _))));
+    }
+
+    #[test]
+    fn test_register_access_requires_debug_capability() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        VMManager::trap_vm(&mut state, vmid, 0, ExitReason::WFI, CPUState::default()).unwrap();
+
+        assert!(Debugger::read_registers(&state, vmid, 0).is_err());
+    }
+
+    #[test]
+    fn test_register_access_rejects_runnable_vm() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(&mut state, vmid, Capability::Debug)
+            .unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+
+        assert!(Debugger::read_registers(&state, vmid, 0).is_err());
+    }
+
+    #[test]
+    fn test_set_and_remove_breakpoint() {
+        let (mut state, vmid) = trapped_vm();
+
+        Debugger::set_breakpoint(&mut state, vmid, 0x4000).unwrap();
+        assert!(state.breakpoints.get(&vmid).unwrap().contains(&0x4000));
+
+        Debugger::remove_breakpoint(&mut state, vmid, 0x4000).unwrap();
+        assert!(!state.breakpoints.get(&vmid).unwrap().contains(&0x4000));
+    }
+
+    #[test]
+    fn test_step_advances_cpu_state_and_stays_debugging() {
+        let (mut state, vmid) = trapped_vm();
+        VMManager::enter_debugging(&mut state, vmid).unwrap();
+
+        let exit_reason = Debugger::step(&mut state, vmid, 0, 0).unwrap();
+        assert!(matches!(exit_reason, ExitReason::WFI));
+        assert!(matches!(state.vms.get(&vmid), Some(VMState::Debugging(_))));
+    }
+
+    #[test]
+    fn test_step_reports_breakpoint_when_pc_matches() {
+        let (mut state, vmid) = trapped_vm();
+        VMManager::enter_debugging(&mut state, vmid).unwrap();
+        // HVF::get_cpu_state's stub always returns a default CPUState
+        // (pc == 0), so registering a breakpoint at 0 reproduces a step
+        // landing on it without needing a real HVF backend.
+        Debugger::set_breakpoint(&mut state, vmid, 0).unwrap();
+
+        let exit_reason = Debugger::step(&mut state, vmid, 0, 0).unwrap();
+        assert!(matches!(exit_reason, ExitReason::Breakpoint));
+    }
+}
@@ -0,0 +1,251 @@
+//! Emulated MMIO device bus for memory-fault dispatch
+//!
+//! Modeled on cloud-hypervisor's `Bus`/`AddressManager`: a [`DeviceBus`]
+//! holds a set of disjoint GPA windows, each bound to a `dyn MmioDevice`
+//! trait object. `ExitHandler::handle_memory_fault` consults the faulting
+//! VM's bus before falling back to injecting a data abort, so emulated
+//! devices can resolve a fault without the guest ever seeing an
+//! exception. Registering a device requires `Capability::MmioAccess`.
+
+use crate::types::{Capability, SystemState, VMID};
+use crate::{Error, Result};
+
+/// A memory-mapped I/O device
+///
+/// `offset` is relative to the start of the device's registered window,
+/// not the absolute guest physical address.
+pub trait MmioDevice: Send {
+    /// Handle a load of `data.len()` bytes at `offset`
+    fn read(&mut self, offset: u64, data: &mut [u8]);
+    /// Handle a store of `data` at `offset`
+    fn write(&mut self, offset: u64, data: &[u8]);
+}
+
+struct DeviceWindow {
+    base: u64,
+    size: u64,
+    device: Box<dyn MmioDevice>,
+}
+
+impl DeviceWindow {
+    fn contains(&self, gpa: u64) -> bool {
+        gpa >= self.base && gpa < self.base + self.size
+    }
+
+    fn overlaps(&self, base: u64, size: u64) -> bool {
+        base < self.base + self.size && self.base < base + size
+    }
+}
+
+/// Device bus: dispatches a faulting GPA to the device registered over
+/// that range, if any
+#[derive(Default)]
+pub struct DeviceBus {
+    windows: Vec<DeviceWindow>,
+}
+
+impl DeviceBus {
+    /// Create an empty device bus
+    pub fn new() -> Self {
+        DeviceBus {
+            windows: Vec::new(),
+        }
+    }
+
+    /// Register `device` over the GPA window `[base, base + size)`
+    ///
+    /// Formal precondition: the window is disjoint from every window
+    /// already registered on this bus.
+    pub fn register(&mut self, base: u64, size: u64, device: Box<dyn MmioDevice>) -> Result<()> {
+        if self.windows.iter().any(|w| w.overlaps(base, size)) {
+            return Err(Error::MemoryError(format!(
+                "MMIO window [{:#x}, {:#x}) overlaps an existing device",
+                base,
+                base + size
+            )));
+        }
+
+        self.windows.push(DeviceWindow { base, size, device });
+        Ok(())
+    }
+
+    /// Dispatch a load from `gpa`; returns `false` if no device is
+    /// registered there
+    pub fn read(&mut self, gpa: u64, data: &mut [u8]) -> bool {
+        match self.windows.iter_mut().find(|w| w.contains(gpa)) {
+            Some(window) => {
+                window.device.read(gpa - window.base, data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Dispatch a store to `gpa`; returns `false` if no device is
+    /// registered there
+    pub fn write(&mut self, gpa: u64, data: &[u8]) -> bool {
+        match self.windows.iter_mut().find(|w| w.contains(gpa)) {
+            Some(window) => {
+                window.device.write(gpa - window.base, data);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Registers emulated devices onto a VM's `DeviceBus`
+pub struct DeviceManager;
+
+impl DeviceManager {
+    /// Register `device` over `[base, base + size)` on `vmid`'s bus
+    ///
+    /// Formal precondition: VM exists, possesses `Capability::MmioAccess`
+    pub fn register_device(
+        state: &mut SystemState,
+        vmid: VMID,
+        base: u64,
+        size: u64,
+        device: Box<dyn MmioDevice>,
+    ) -> Result<()> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+
+        if !state.has_capability(vmid, Capability::MmioAccess) {
+            return Err(Error::CapabilityError(
+                "MmioAccess capability required".to_string(),
+            ));
+        }
+
+        state
+            .devices
+            .entry(vmid)
+            .or_default()
+            .register(base, size, device)
+    }
+}
+
+/// A minimal debug-console UART, serving as the reference `MmioDevice`
+///
+/// Writes to offset 0 are appended to [`Uart::output`]; reads of the line
+/// status register (offset 5) always report the transmitter as ready, so
+/// a polling guest driver never blocks.
+#[derive(Default)]
+pub struct Uart {
+    /// Bytes written to the console so far
+    pub output: Vec<u8>,
+}
+
+const UART_LINE_STATUS_OFFSET: u64 = 5;
+const UART_LINE_STATUS_TX_READY: u8 = 0x20;
+
+impl MmioDevice for Uart {
+    fn read(&mut self, offset: u64, data: &mut [u8]) {
+        data.fill(0);
+        if offset == UART_LINE_STATUS_OFFSET && !data.is_empty() {
+            data[0] = UART_LINE_STATUS_TX_READY;
+        }
+    }
+
+    fn write(&mut self, offset: u64, data: &[u8]) {
+        if offset == 0 {
+            self.output.extend_from_slice(data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exit::ExitHandler;
+    use crate::types::{CPUState, ExitReason, GPA};
+    use crate::vm::VMManager;
+
+    fn mmio_vm() -> (SystemState, VMID) {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            Capability::MmioAccess,
+        )
+        .unwrap();
+        (state, vmid)
+    }
+
+    #[test]
+    fn test_register_device_requires_capability() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
+        let result = DeviceManager::register_device(&mut state, vmid, 0x9000, 0x10, Box::new(Uart::default()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_register_rejects_overlapping_windows() {
+        let (mut state, vmid) = mmio_vm();
+        DeviceManager::register_device(&mut state, vmid, 0x9000, 0x10, Box::new(Uart::default()))
+            .unwrap();
+
+        let result =
+            DeviceManager::register_device(&mut state, vmid, 0x9008, 0x10, Box::new(Uart::default()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_uart_accepts_write_and_reports_tx_ready() {
+        let (mut state, vmid) = mmio_vm();
+        DeviceManager::register_device(&mut state, vmid, 0x9000, 0x10, Box::new(Uart::default()))
+            .unwrap();
+
+        assert!(state.devices.get_mut(&vmid).unwrap().write(0x9000, b"hi"));
+
+        let mut buf = [0u8; 1];
+        assert!(state.devices.get_mut(&vmid).unwrap().read(0x9005, &mut buf));
+        assert_eq!(buf[0], UART_LINE_STATUS_TX_READY);
+    }
+
+    #[test]
+    fn test_memory_fault_dispatches_to_device() {
+        let (mut state, vmid) = mmio_vm();
+        DeviceManager::register_device(&mut state, vmid, 0x9000, 0x10, Box::new(Uart::default()))
+            .unwrap();
+
+        let cpu_state = CPUState {
+            pc: 0x1000,
+            ..Default::default()
+        };
+        let exit_reason = ExitReason::MemoryFault {
+            gpa: GPA(0x9005),
+            write: false,
+        };
+
+        let action = ExitHandler::handle_exit(&mut state, vmid, &exit_reason, &cpu_state).unwrap();
+        match action {
+            crate::exit::ExitAction::Resume(new_state) => {
+                assert_eq!(new_state.pc, 0x1004);
+                assert_eq!(new_state.gpr[0] as u8, UART_LINE_STATUS_TX_READY);
+            }
+            other => panic!("expected Resume, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_memory_fault_falls_through_when_unmapped() {
+        let (mut state, vmid) = mmio_vm();
+
+        let cpu_state = CPUState::default();
+        let exit_reason = ExitReason::MemoryFault {
+            gpa: GPA(0x1000),
+            write: false,
+        };
+
+        let action = ExitHandler::handle_exit(&mut state, vmid, &exit_reason, &cpu_state).unwrap();
+        assert!(matches!(
+            action,
+            crate::exit::ExitAction::InjectException { vector: 1, .. }
+        ));
+    }
+}
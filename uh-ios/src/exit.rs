@@ -21,7 +21,15 @@ pub enum ExitAction {
     /// Halt VM permanently
     Halt,
     /// Inject exception back to guest
-    InjectException { vector: u32, cpu_state: CPUState },
+    InjectException {
+        /// Exception vector to inject
+        vector: u32,
+        /// CPU state to resume with after injection
+        cpu_state: CPUState,
+    },
+    /// Move the VM into `Debugging` state for interactive debugging, e.g.
+    /// after a breakpoint hit
+    Suspend,
 }
 
 impl ExitHandler {
@@ -34,7 +42,7 @@ impl ExitHandler {
     ///
     /// This is the core of the verified exit handling system.
     pub fn handle_exit(
-        state: &SystemState,
+        state: &mut SystemState,
         vmid: VMID,
         exit_reason: &ExitReason,
         cpu_state: &CPUState,
@@ -81,17 +89,22 @@ impl ExitHandler {
             ExitReason::Cancelled => {
                 Self::handle_cancelled(state, vmid, cpu_state)
             }
+
+            ExitReason::Breakpoint => {
+                Self::handle_breakpoint(state, vmid, cpu_state)
+            }
         }
     }
     
     /// Handle hypercall from guest
     ///
-    /// Deterministic: Same hypercall number and arguments → same action
+    /// Deterministic: Same hypercall number and arguments → same action,
+    /// given the same mailbox contents.
     fn handle_hypercall(
-        _state: &SystemState,
-        _vmid: VMID,
+        state: &mut SystemState,
+        vmid: VMID,
         nr: u64,
-        _args: &[u64; 6],
+        args: &[u64; 6],
         cpu_state: &CPUState,
     ) -> Result<ExitAction> {
         match nr {
@@ -101,12 +114,51 @@ impl ExitHandler {
                 new_state.pc += 4; // Advance past hypercall instruction
                 Ok(ExitAction::Resume(new_state))
             }
-            
+
             // Hypercall 1: Halt
             1 => {
                 Ok(ExitAction::Halt)
             }
-            
+
+            // Hypercall 2: vsock-style send. args = [target_vmid, gpa, len]
+            2 => {
+                let target = crate::types::VMID(args[0] as u32);
+                let gpa = crate::types::GPA(args[1]);
+                let len = args[2] as usize;
+
+                match crate::vsock::Vsock::send(state, vmid, target, gpa, len) {
+                    Ok(copied) => {
+                        let mut new_state = cpu_state.clone();
+                        new_state.gpr[0] = copied as u64;
+                        new_state.pc += 4;
+                        Ok(ExitAction::Resume(new_state))
+                    }
+                    Err(_) => Ok(ExitAction::InjectException {
+                        vector: 0, // Undefined instruction
+                        cpu_state: cpu_state.clone(),
+                    }),
+                }
+            }
+
+            // Hypercall 3: vsock-style receive. args = [gpa, max_len]
+            3 => {
+                let gpa = crate::types::GPA(args[0]);
+                let max_len = args[1] as usize;
+
+                match crate::vsock::Vsock::receive(state, vmid, gpa, max_len) {
+                    Ok(received) => {
+                        let mut new_state = cpu_state.clone();
+                        new_state.gpr[0] = received as u64;
+                        new_state.pc += 4;
+                        Ok(ExitAction::Resume(new_state))
+                    }
+                    Err(_) => Ok(ExitAction::InjectException {
+                        vector: 0, // Undefined instruction
+                        cpu_state: cpu_state.clone(),
+                    }),
+                }
+            }
+
             // Unknown hypercalls: Inject undefined instruction exception
             _ => {
                 Ok(ExitAction::InjectException {
@@ -119,15 +171,52 @@ impl ExitHandler {
     
     /// Handle memory fault
     ///
-    /// Deterministic: Same GPA and access type → same action
+    /// Deterministic: Same GPA and access type → same action, given the
+    /// same device bus contents.
+    ///
+    /// If the faulting GPA falls inside a device window registered on
+    /// the VM's `DeviceBus`, the access is emulated and execution resumes
+    /// past the faulting instruction. Only unmapped faults fall through
+    /// to the data-abort path.
     fn handle_memory_fault(
-        _state: &SystemState,
-        _vmid: VMID,
-        _gpa: crate::types::GPA,
-        _write: bool,
+        state: &mut SystemState,
+        vmid: VMID,
+        gpa: crate::types::GPA,
+        write: bool,
         cpu_state: &CPUState,
     ) -> Result<ExitAction> {
-        // Memory faults are injected back to guest as data abort
+        if let Some(bus) = state.devices.get_mut(&vmid) {
+            let mut new_state = cpu_state.clone();
+
+            // Decode the load/store from cpu_state: the transferred value
+            // is always x0 and the access width is a 32-bit word, which
+            // covers the common case of device-register accesses.
+            if write {
+                let word = cpu_state.gpr[0] as u32;
+                if bus.write(gpa.0, &word.to_le_bytes()) {
+                    new_state.pc += 4;
+                    return Ok(ExitAction::Resume(new_state));
+                }
+            } else {
+                let mut buf = [0u8; 4];
+                if bus.read(gpa.0, &mut buf) {
+                    new_state.gpr[0] = u32::from_le_bytes(buf) as u64;
+                    new_state.pc += 4;
+                    return Ok(ExitAction::Resume(new_state));
+                }
+            }
+        }
+
+        // A fault inside a freshly hot-added-but-unbacked region is
+        // resolved by lazily mapping a host page; the guest retries the
+        // faulting access once the mapping exists, so the CPU state is
+        // resumed unchanged rather than advancing past it.
+        if crate::memory::MemoryManager::resolve_lazy_fault(state, vmid, gpa)? {
+            return Ok(ExitAction::Resume(cpu_state.clone()));
+        }
+
+        // Memory faults with no backing device or lazily-mappable region
+        // are injected back to the guest as a data abort
         Ok(ExitAction::InjectException {
             vector: 1, // Data abort
             cpu_state: cpu_state.clone(),
@@ -209,51 +298,74 @@ impl ExitHandler {
         // Cancelled VMs are halted
         Ok(ExitAction::Halt)
     }
-    
+
+    /// Handle a breakpoint hit
+    ///
+    /// Deterministic: Always suspends for debugging, leaving registers
+    /// stable for [`crate::debugger::Debugger`] to inspect
+    fn handle_breakpoint(
+        _state: &SystemState,
+        _vmid: VMID,
+        _cpu_state: &CPUState,
+    ) -> Result<ExitAction> {
+        Ok(ExitAction::Suspend)
+    }
+
     /// Process the next pending exit from the queue
     ///
-    /// This is the main entry point for the exit processing loop.
-    pub fn process_next_exit(state: &mut SystemState) -> Result<Option<(VMID, ExitAction)>> {
+    /// This is the main entry point for the exit processing loop. The
+    /// returned vCPU index identifies which of the VM's per-vCPU CPU
+    /// states trapped, and must be passed back to
+    /// [`ExitHandler::apply_exit_action`].
+    pub fn process_next_exit(state: &mut SystemState) -> Result<Option<(VMID, u32, ExitAction)>> {
         // Pop next exit from queue
-        let Some((vmid, exit_reason)) = state.exits.pop_front() else {
+        let Some((vmid, vcpu, exit_reason)) = state.exits.pop_front() else {
             return Ok(None); // No pending exits
         };
-        
-        // Get current VM state to extract CPU state
+
+        // Get current VM state to extract the trapping vCPU's CPU state
         let vm_state = VMManager::get_vm_state(state, vmid)?;
-        
+
         let cpu_state = match vm_state {
-            crate::types::VMState::Trapped(_, cpu) => cpu.clone(),
+            crate::types::VMState::Trapped(_, cpus) => cpus
+                .get(vcpu as usize)
+                .cloned()
+                .ok_or(Error::InvalidVMState(vmid))?,
             _ => return Err(Error::InvalidVMState(vmid)),
         };
-        
+
         // Handle exit deterministically
         let action = Self::handle_exit(state, vmid, &exit_reason, &cpu_state)?;
-        
-        Ok(Some((vmid, action)))
+
+        Ok(Some((vmid, vcpu, action)))
     }
-    
+
     /// Apply exit action to VM state
     ///
     /// This modifies the system state based on the exit action result.
     pub fn apply_exit_action(
         state: &mut SystemState,
         vmid: VMID,
+        vcpu: u32,
         action: ExitAction,
     ) -> Result<()> {
         match action {
             ExitAction::Resume(cpu_state) => {
-                VMManager::resume_vm(state, vmid, cpu_state)
+                VMManager::resume_vm(state, vmid, vcpu, cpu_state)
             }
-            
+
             ExitAction::Halt => {
                 VMManager::halt_vm(state, vmid)
             }
-            
+
             ExitAction::InjectException { cpu_state, .. } => {
                 // For now, we resume with the CPU state
                 // A full implementation would inject the exception into guest's vector table
-                VMManager::resume_vm(state, vmid, cpu_state)
+                VMManager::resume_vm(state, vmid, vcpu, cpu_state)
+            }
+
+            ExitAction::Suspend => {
+                VMManager::enter_debugging(state, vmid)
             }
         }
     }
@@ -267,7 +379,7 @@ mod tests {
     #[test]
     fn test_exit_handler_totality() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         let cpu_state = CPUState::default();
         
@@ -280,10 +392,11 @@ mod tests {
             ExitReason::WFI,
             ExitReason::Exception { vector: 0 },
             ExitReason::Cancelled,
+            ExitReason::Breakpoint,
         ];
         
         for exit_reason in exit_reasons {
-            let result = ExitHandler::handle_exit(&state, vmid, &exit_reason, &cpu_state);
+            let result = ExitHandler::handle_exit(&mut state, vmid, &exit_reason, &cpu_state);
             assert!(result.is_ok(), "Exit reason {:?} not handled", exit_reason);
         }
     }
@@ -291,14 +404,14 @@ mod tests {
     #[test]
     fn test_exit_handler_determinism() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         let cpu_state = CPUState::default();
         let exit_reason = ExitReason::WFI;
         
         // Call handler twice with same inputs
-        let action1 = ExitHandler::handle_exit(&state, vmid, &exit_reason, &cpu_state).unwrap();
-        let action2 = ExitHandler::handle_exit(&state, vmid, &exit_reason, &cpu_state).unwrap();
+        let action1 = ExitHandler::handle_exit(&mut state, vmid, &exit_reason, &cpu_state).unwrap();
+        let action2 = ExitHandler::handle_exit(&mut state, vmid, &exit_reason, &cpu_state).unwrap();
         
         // Results should be identical (determinism)
         match (action1, action2) {
@@ -312,33 +425,159 @@ mod tests {
     #[test]
     fn test_hypercall_halt() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         let cpu_state = CPUState::default();
         let exit_reason = ExitReason::Hypercall { nr: 1, args: [0; 6] };
         
-        let action = ExitHandler::handle_exit(&state, vmid, &exit_reason, &cpu_state).unwrap();
+        let action = ExitHandler::handle_exit(&mut state, vmid, &exit_reason, &cpu_state).unwrap();
         
         assert!(matches!(action, ExitAction::Halt));
     }
 
+    #[test]
+    fn test_breakpoint_suspends_for_debugging() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            crate::types::Capability::Debug,
+        )
+        .unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        VMManager::trap_vm(&mut state, vmid, 0, ExitReason::Breakpoint, CPUState::default())
+            .unwrap();
+
+        let action =
+            ExitHandler::handle_exit(&mut state, vmid, &ExitReason::Breakpoint, &CPUState::default())
+                .unwrap();
+        assert!(matches!(action, ExitAction::Suspend));
+
+        ExitHandler::apply_exit_action(&mut state, vmid, 0, action).unwrap();
+        assert!(matches!(
+            state.vms.get(&vmid),
+            Some(crate::types::VMState::Debugging(_))
+        ));
+    }
+
+    #[test]
+    fn test_vsock_send_receive_via_hypercall() {
+        let mut state = SystemState::new();
+        let sender = VMManager::create_vm(&mut state, 1).unwrap();
+        let target = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            sender,
+            Capability::SendTo(target),
+        )
+        .unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            target,
+            Capability::ReceiveFrom(sender),
+        )
+        .unwrap();
+
+        let cpu_state = CPUState::default();
+        let send = ExitReason::Hypercall {
+            nr: 2,
+            args: [target.0 as u64, 0x1000, 8, 0, 0, 0],
+        };
+
+        let action = ExitHandler::handle_exit(&mut state, sender, &send, &cpu_state).unwrap();
+        match action {
+            ExitAction::Resume(new_state) => assert_eq!(new_state.gpr[0], 8),
+            other => panic!("expected Resume, got {:?}", other),
+        }
+
+        let receive = ExitReason::Hypercall {
+            nr: 3,
+            args: [0x2000, 64, 0, 0, 0, 0],
+        };
+        let action = ExitHandler::handle_exit(&mut state, target, &receive, &cpu_state).unwrap();
+        match action {
+            ExitAction::Resume(new_state) => assert_eq!(new_state.gpr[0], 8),
+            other => panic!("expected Resume, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vsock_send_without_capability_injects_exception() {
+        let mut state = SystemState::new();
+        let sender = VMManager::create_vm(&mut state, 1).unwrap();
+        let target = VMManager::create_vm(&mut state, 1).unwrap();
+
+        let cpu_state = CPUState::default();
+        let send = ExitReason::Hypercall {
+            nr: 2,
+            args: [target.0 as u64, 0x1000, 8, 0, 0, 0],
+        };
+
+        let action = ExitHandler::handle_exit(&mut state, sender, &send, &cpu_state).unwrap();
+        assert!(matches!(
+            action,
+            ExitAction::InjectException { vector: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_memory_fault_resolves_lazily_in_hot_added_region() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            Capability::HotplugMemory,
+        )
+        .unwrap();
+        crate::memory::MemoryManager::hotplug_region(
+            &mut state,
+            vmid,
+            crate::memory::MemoryRegion {
+                gpa: crate::types::GPA(0x10000),
+                size: 0x1000,
+            },
+            crate::memory::DEFAULT_PHYS_ADDR_BITS,
+        )
+        .unwrap();
+
+        let cpu_state = CPUState {
+            pc: 0x2000,
+            ..Default::default()
+        };
+        let exit_reason = ExitReason::MemoryFault {
+            gpa: crate::types::GPA(0x10010),
+            write: false,
+        };
+
+        let action = ExitHandler::handle_exit(&mut state, vmid, &exit_reason, &cpu_state).unwrap();
+        match action {
+            // The faulting instruction is retried unchanged, now that the
+            // region is backed.
+            ExitAction::Resume(new_state) => assert_eq!(new_state.pc, 0x2000),
+            other => panic!("expected Resume, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_process_exit_queue() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
         
         let cpu_state = CPUState::default();
-        VMManager::initialize_vm(&mut state, vmid, cpu_state.clone()).unwrap();
-        
+        VMManager::initialize_vm(&mut state, vmid, vec![cpu_state.clone()]).unwrap();
+
         // Trap VM with WFI
-        VMManager::trap_vm(&mut state, vmid, ExitReason::WFI, cpu_state).unwrap();
-        
+        VMManager::trap_vm(&mut state, vmid, 0, ExitReason::WFI, cpu_state).unwrap();
+
         // Process exit
         let result = ExitHandler::process_next_exit(&mut state).unwrap();
         assert!(result.is_some());
-        
-        let (exit_vmid, action) = result.unwrap();
+
+        let (exit_vmid, vcpu, action) = result.unwrap();
         assert_eq!(exit_vmid, vmid);
+        assert_eq!(vcpu, 0);
         assert!(matches!(action, ExitAction::Resume(_)));
     }
 }
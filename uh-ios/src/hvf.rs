@@ -115,6 +115,16 @@ impl HVF {
         Ok(0) // Stub VCPU handle
     }
     
+    /// Create `count` virtual CPUs for an SMP guest
+    ///
+    /// In real implementation, calls hv_vcpu_create() once per vCPU and
+    /// tracks the resulting set of handles against `ctx`. The stub hands
+    /// out sequential handles, one per vCPU index, rather than the single
+    /// fixed handle [`HVF::create_vcpu`] uses for the single-vCPU case.
+    pub fn create_vcpus(_ctx: HVFContext, count: u32) -> Result<Vec<u64>> {
+        Ok((0..count as u64).collect())
+    }
+
     /// Destroy a virtual CPU
     ///
     /// In real implementation, calls hv_vcpu_destroy()
@@ -162,6 +172,11 @@ impl HVF {
     /// This is modeled as a nondeterministic execution oracle.
     /// In real implementation, calls hv_vcpu_run()
     ///
+    /// Executes under the `Vcpu` [`crate::sandbox::ThreadCategory`]
+    /// filter: the calling thread is expected to have already installed
+    /// one via [`crate::sandbox::apply_filter`] before its first call
+    /// here, as [`crate::cpu::CpuManager`]'s run loop does.
+    ///
     /// Returns the exit reason when VM exits to hypervisor.
     pub fn run_vcpu(_vcpu: u64) -> Result<ExitReason> {
         // In real implementation:
@@ -174,7 +189,47 @@ impl HVF {
         // Stub: Return WFI for modeling
         Ok(ExitReason::WFI)
     }
-    
+
+    /// Single-step a virtual CPU by exactly one guest instruction
+    ///
+    /// This is modeled as a nondeterministic execution oracle, like
+    /// [`HVF::run_vcpu`], but configured to trap back after one
+    /// instruction instead of running freely.
+    /// In real implementation, arms the ARM debug architecture's software
+    /// step bit before calling hv_vcpu_run(), so control returns to the
+    /// host after exactly one guest instruction.
+    ///
+    /// Returns the exit reason for the instruction that was stepped.
+    pub fn run_vcpu_single_step(_vcpu: u64) -> Result<ExitReason> {
+        // Stub: model a completed single step as a WFI exit, same as
+        // run_vcpu's stub oracle.
+        Ok(ExitReason::WFI)
+    }
+
+    /// Read guest memory through the mapping installed by `map_memory`
+    ///
+    /// In real implementation, reads directly from the host mapping
+    /// backing `guest_addr` (HVF shares the host address space with the
+    /// guest via `hv_vm_map`, so no syscall is needed per access).
+    pub fn read_guest_memory(_ctx: HVFContext, _guest_addr: u64, len: usize) -> Result<Vec<u8>> {
+        // In real implementation:
+        // unsafe { std::slice::from_raw_parts(host_addr_for(guest_addr), len) }.to_vec()
+
+        Ok(vec![0u8; len])
+    }
+
+    /// Write guest memory through the mapping installed by `map_memory`
+    ///
+    /// In real implementation, writes directly into the host mapping
+    /// backing `guest_addr`.
+    pub fn write_guest_memory(_ctx: HVFContext, _guest_addr: u64, _data: &[u8]) -> Result<()> {
+        // In real implementation:
+        // unsafe { std::slice::from_raw_parts_mut(host_addr_for(guest_addr), data.len()) }
+        //     .copy_from_slice(data);
+
+        Ok(())
+    }
+
     /// Get system information from HVF
     ///
     /// In real implementation, checks HVF availability and capabilities
@@ -188,6 +243,7 @@ impl HVF {
             hvf_available: true,
             arm_el2_supported: true,
             max_vcpus: 8,
+            phys_addr_bits: crate::memory::DEFAULT_PHYS_ADDR_BITS as u8,
         })
     }
 }
@@ -201,6 +257,13 @@ pub struct SystemInfo {
     pub arm_el2_supported: bool,
     /// Maximum number of VCPUs supported
     pub max_vcpus: u32,
+    /// Host physical-address width, in bits, as queried from HVF/the vCPU
+    ///
+    /// Mirrors cloud-hypervisor's `get_host_cpu_phys_bits`: used by
+    /// [`crate::vm::VMManager::configure_address_space`] to clamp a
+    /// caller-requested guest physical address width to what the host
+    /// hardware actually supports.
+    pub phys_addr_bits: u8,
 }
 
 #[cfg(test)]
@@ -252,5 +315,6 @@ mod tests {
         // In stub implementation, these are hardcoded
         assert!(info.hvf_available);
         assert!(info.arm_el2_supported);
+        assert_eq!(info.phys_addr_bits, crate::memory::DEFAULT_PHYS_ADDR_BITS as u8);
     }
 }
@@ -23,6 +23,14 @@
 //! - `capability`: Capability enforcement
 //! - `exit`: VM exit dispatching and handling
 //! - `hvf`: Hypervisor.framework FFI bindings
+//! - `migration`: Snapshot, restore, and live migration of `SystemState`
+//! - `debug`: GDB Remote Serial Protocol debug stub
+//! - `debugger`: Interactive vCPU debugging over the `VMManager` state machine
+//! - `device`: Emulated MMIO device bus for memory-fault dispatch
+//! - `cpu`: Per-vCPU thread run loop driving the exit queue
+//! - `vsock`: Capability-gated inter-VM message channel
+//! - `coredump`: ELF64 guest coredump generation from trapped/halted VM state
+//! - `sandbox`: Per-thread seccomp filters for vCPU and VMM threads
 
 #![deny(unsafe_op_in_unsafe_fn)]
 #![warn(missing_docs)]
@@ -33,6 +41,14 @@ pub mod memory;
 pub mod capability;
 pub mod exit;
 pub mod hvf;
+pub mod migration;
+pub mod debug;
+pub mod debugger;
+pub mod device;
+pub mod cpu;
+pub mod vsock;
+pub mod coredump;
+pub mod sandbox;
 
 pub use types::{SystemState, VMState, VMID, Capability};
 
@@ -0,0 +1,482 @@
+//! Memory mapping and isolation
+//!
+//! This module enforces the memory non-interference invariant: distinct
+//! VMs have disjoint memory regions. Every path that adds regions to a
+//! live `SystemState` ([`MemoryManager::map_region`],
+//! [`MemoryManager::hotplug_region`] and
+//! [`crate::vm::VMManager::restore_vm`]) checks disjointness against every
+//! other VM's regions as well as this VM's existing regions before the
+//! region is admitted.
+
+use serde::{Deserialize, Serialize};
+
+use crate::hvf::HVF;
+use crate::types::{Capability, SystemState, GPA, VMID};
+use crate::{Error, Result};
+
+/// Conservative default guest physical-address width, in bits
+///
+/// Mirrors cloud-hypervisor's memory manager sizing the guest physical
+/// address space from the vCPU's maximum physical-address bits, clamped
+/// to what the host actually supports. Callers that know the real
+/// host/vCPU limit (e.g. from `HVF::get_system_info`) should pass it to
+/// [`MemoryManager::hotplug_region`] instead of relying on this default.
+pub const DEFAULT_PHYS_ADDR_BITS: u32 = 40;
+
+/// A mapped guest physical memory region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryRegion {
+    /// Guest physical address of the start of the region
+    pub gpa: GPA,
+    /// Size of the region in bytes
+    pub size: u64,
+}
+
+impl MemoryRegion {
+    /// Exclusive end address of the region
+    pub fn end(&self) -> u64 {
+        self.gpa.0 + self.size
+    }
+
+    /// Whether this region overlaps another
+    pub fn overlaps(&self, other: &MemoryRegion) -> bool {
+        self.gpa.0 < other.end() && other.gpa.0 < self.end()
+    }
+}
+
+/// Memory manager enforcing the non-interference invariant
+pub struct MemoryManager;
+
+impl MemoryManager {
+    /// Map a new memory region into a VM's guest physical address space
+    ///
+    /// Formal precondition: VM exists, possesses `MapMemory` capability,
+    /// `region` is disjoint from every other VM's regions and from this
+    /// VM's existing regions, and `region.end()` does not exceed the
+    /// ceiling configured by
+    /// [`crate::vm::VMManager::configure_address_space`], if any.
+    /// Formal postcondition: region is registered for the VM.
+    pub fn map_region(state: &mut SystemState, vmid: VMID, region: MemoryRegion) -> Result<()> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+
+        if !state.has_capability(vmid, Capability::MapMemory) {
+            return Err(Error::CapabilityError(
+                "MapMemory capability required".to_string(),
+            ));
+        }
+
+        if let Some(&bits) = state.addr_space_bits.get(&vmid) {
+            let phys_addr_limit = 1u64 << bits;
+            if region.end() > phys_addr_limit {
+                return Err(Error::MemoryError(format!(
+                    "region {:?} exceeds configured {}-bit physical address space",
+                    region, bits
+                )));
+            }
+        }
+
+        Self::check_disjoint(state, vmid, &region)?;
+
+        state.memory.entry(vmid).or_default().push(region);
+
+        Ok(())
+    }
+
+    /// Verify that `region` does not overlap any region already mapped for
+    /// `vmid` or for any other VM (memory non-interference).
+    pub(crate) fn check_disjoint(
+        state: &SystemState,
+        vmid: VMID,
+        region: &MemoryRegion,
+    ) -> Result<()> {
+        for (&other_vmid, regions) in state.memory.iter() {
+            if other_vmid != vmid && regions.iter().any(|r| r.overlaps(region)) {
+                return Err(Error::MemoryError(format!(
+                    "region {:?} overlaps VM {:?}",
+                    region, other_vmid
+                )));
+            }
+        }
+
+        if let Some(regions) = state.memory.get(&vmid) {
+            if regions.iter().any(|r| r.overlaps(region)) {
+                return Err(Error::MemoryError(format!(
+                    "region {:?} overlaps an existing region for VM {:?}",
+                    region, vmid
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Unmap a previously mapped region from a VM
+    ///
+    /// Formal precondition: VM exists
+    /// Formal postcondition: region removed from the VM's region set, if present
+    pub fn unmap_region(state: &mut SystemState, vmid: VMID, region: MemoryRegion) -> Result<()> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+        if let Some(regions) = state.memory.get_mut(&vmid) {
+            regions.retain(|r| *r != region);
+        }
+        Ok(())
+    }
+
+    /// Get all memory regions mapped for a VM
+    pub fn get_regions(state: &SystemState, vmid: VMID) -> Vec<MemoryRegion> {
+        state.memory.get(&vmid).cloned().unwrap_or_default()
+    }
+
+    /// Hot-add a new guest physical memory region into a running VM
+    ///
+    /// Formal precondition: VM exists, possesses `HotplugMemory`
+    /// capability, `region` is disjoint from every other VM's regions and
+    /// from this VM's existing regions, and `region.end()` does not
+    /// exceed the guest physical address space implied by `phys_bits`.
+    /// Formal postcondition: region is registered for the VM but left
+    /// unbacked; the first fault against it is resolved lazily by
+    /// [`MemoryManager::resolve_lazy_fault`].
+    pub fn hotplug_region(
+        state: &mut SystemState,
+        vmid: VMID,
+        region: MemoryRegion,
+        phys_bits: u32,
+    ) -> Result<()> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+
+        if !state.has_capability(vmid, Capability::HotplugMemory) {
+            return Err(Error::CapabilityError(
+                "HotplugMemory capability required".to_string(),
+            ));
+        }
+
+        let phys_addr_limit = 1u64 << phys_bits;
+        if region.end() > phys_addr_limit {
+            return Err(Error::MemoryError(format!(
+                "region {:?} exceeds {}-bit physical address space",
+                region, phys_bits
+            )));
+        }
+
+        Self::check_disjoint(state, vmid, &region)?;
+
+        state.memory.entry(vmid).or_default().push(region);
+        state.unbacked_regions.entry(vmid).or_default().push(region.gpa);
+
+        Ok(())
+    }
+
+    /// Hot-remove a previously hot-added region from a running VM
+    ///
+    /// Formal precondition: VM exists, possesses `HotplugMemory`
+    /// capability.
+    /// Formal postcondition: region removed from the VM's region set and
+    /// from the unbacked-region tracking, if present.
+    pub fn hot_remove_region(state: &mut SystemState, vmid: VMID, region: MemoryRegion) -> Result<()> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+
+        if !state.has_capability(vmid, Capability::HotplugMemory) {
+            return Err(Error::CapabilityError(
+                "HotplugMemory capability required".to_string(),
+            ));
+        }
+
+        if let Some(regions) = state.memory.get_mut(&vmid) {
+            regions.retain(|r| *r != region);
+        }
+        if let Some(unbacked) = state.unbacked_regions.get_mut(&vmid) {
+            unbacked.retain(|gpa| *gpa != region.gpa);
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a memory fault that lands inside a hot-added-but-unbacked
+    /// region by lazily mapping a host page for it
+    ///
+    /// Returns `true` if `gpa` fell inside such a region and was mapped,
+    /// `false` if no unbacked region covers `gpa` (the caller should fall
+    /// through to injecting a data abort).
+    pub fn resolve_lazy_fault(state: &mut SystemState, vmid: VMID, gpa: GPA) -> Result<bool> {
+        let Some(region) = state
+            .memory
+            .get(&vmid)
+            .and_then(|regions| regions.iter().find(|r| r.gpa.0 <= gpa.0 && gpa.0 < r.end()))
+            .copied()
+        else {
+            return Ok(false);
+        };
+
+        let is_unbacked = state
+            .unbacked_regions
+            .get(&vmid)
+            .is_some_and(|unbacked| unbacked.contains(&region.gpa));
+        if !is_unbacked {
+            return Ok(false);
+        }
+
+        let ctx = HVF::create_vm(vmid)?;
+        HVF::map_memory(ctx, 0, region.gpa.0, region.size, 0)?;
+
+        if let Some(unbacked) = state.unbacked_regions.get_mut(&vmid) {
+            unbacked.retain(|base| *base != region.gpa);
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::VMManager;
+
+    #[test]
+    fn test_map_region() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
+        let region = MemoryRegion {
+            gpa: GPA(0x1000),
+            size: 0x1000,
+        };
+        MemoryManager::map_region(&mut state, vmid, region).unwrap();
+
+        assert_eq!(MemoryManager::get_regions(&state, vmid), vec![region]);
+    }
+
+    #[test]
+    fn test_disjoint_regions_enforced_within_vm() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
+        MemoryManager::map_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA(0x1000),
+                size: 0x1000,
+            },
+        )
+        .unwrap();
+
+        let result = MemoryManager::map_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA(0x1800),
+                size: 0x1000,
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_disjoint_regions_enforced_across_vms() {
+        let mut state = SystemState::new();
+        let vm1 = VMManager::create_vm(&mut state, 1).unwrap();
+        let vm2 = VMManager::create_vm(&mut state, 1).unwrap();
+
+        MemoryManager::map_region(
+            &mut state,
+            vm1,
+            MemoryRegion {
+                gpa: GPA(0x1000),
+                size: 0x1000,
+            },
+        )
+        .unwrap();
+
+        let result = MemoryManager::map_region(
+            &mut state,
+            vm2,
+            MemoryRegion {
+                gpa: GPA(0x1800),
+                size: 0x1000,
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_unmap_region() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
+        let region = MemoryRegion {
+            gpa: GPA(0x1000),
+            size: 0x1000,
+        };
+        MemoryManager::map_region(&mut state, vmid, region).unwrap();
+        MemoryManager::unmap_region(&mut state, vmid, region).unwrap();
+
+        assert!(MemoryManager::get_regions(&state, vmid).is_empty());
+    }
+
+    #[test]
+    fn test_map_region_rejects_region_above_configured_ceiling() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::configure_address_space(&mut state, vmid, Some(12)).unwrap();
+
+        let result = MemoryManager::map_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA((1u64 << 12) - 0x800),
+                size: 0x1000,
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_map_region_allows_region_within_configured_ceiling() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::configure_address_space(&mut state, vmid, Some(16)).unwrap();
+
+        let region = MemoryRegion {
+            gpa: GPA(0x1000),
+            size: 0x1000,
+        };
+        MemoryManager::map_region(&mut state, vmid, region).unwrap();
+
+        assert_eq!(MemoryManager::get_regions(&state, vmid), vec![region]);
+    }
+
+    #[test]
+    fn test_map_region_requires_capability() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        state
+            .caps
+            .get_mut(&vmid)
+            .unwrap()
+            .remove(&Capability::MapMemory);
+
+        let result = MemoryManager::map_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA(0x1000),
+                size: 0x1000,
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    fn hotplug_vm() -> (SystemState, VMID) {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            Capability::HotplugMemory,
+        )
+        .unwrap();
+        (state, vmid)
+    }
+
+    #[test]
+    fn test_hotplug_region_requires_capability() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
+        let result = MemoryManager::hotplug_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA(0x1000),
+                size: 0x1000,
+            },
+            DEFAULT_PHYS_ADDR_BITS,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hotplug_region_rejects_disjointness_violation() {
+        let (mut state, vmid) = hotplug_vm();
+        MemoryManager::map_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA(0x1000),
+                size: 0x1000,
+            },
+        )
+        .unwrap();
+
+        let result = MemoryManager::hotplug_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA(0x1800),
+                size: 0x1000,
+            },
+            DEFAULT_PHYS_ADDR_BITS,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hotplug_region_rejects_phys_bits_overflow() {
+        let (mut state, vmid) = hotplug_vm();
+
+        let result = MemoryManager::hotplug_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: GPA((1u64 << 12) - 0x800),
+                size: 0x1000,
+            },
+            12,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hotplug_region_starts_unbacked_and_resolves_lazily() {
+        let (mut state, vmid) = hotplug_vm();
+        let region = MemoryRegion {
+            gpa: GPA(0x10000),
+            size: 0x1000,
+        };
+        MemoryManager::hotplug_region(&mut state, vmid, region, DEFAULT_PHYS_ADDR_BITS).unwrap();
+
+        assert!(state.unbacked_regions.get(&vmid).unwrap().contains(&region.gpa));
+
+        let resolved = MemoryManager::resolve_lazy_fault(&mut state, vmid, GPA(0x10010)).unwrap();
+        assert!(resolved);
+        assert!(!state.unbacked_regions.get(&vmid).unwrap().contains(&region.gpa));
+
+        // A second fault in the same (now backed) region is not lazily
+        // resolved again
+        let resolved_again = MemoryManager::resolve_lazy_fault(&mut state, vmid, GPA(0x10010)).unwrap();
+        assert!(!resolved_again);
+    }
+
+    #[test]
+    fn test_hot_remove_region() {
+        let (mut state, vmid) = hotplug_vm();
+        let region = MemoryRegion {
+            gpa: GPA(0x10000),
+            size: 0x1000,
+        };
+        MemoryManager::hotplug_region(&mut state, vmid, region, DEFAULT_PHYS_ADDR_BITS).unwrap();
+
+        MemoryManager::hot_remove_region(&mut state, vmid, region).unwrap();
+
+        assert!(MemoryManager::get_regions(&state, vmid).is_empty());
+        assert!(!state.unbacked_regions.get(&vmid).unwrap().contains(&region.gpa));
+    }
+}
@@ -0,0 +1,399 @@
+//! Snapshot, restore, and live migration of `SystemState`
+//!
+//! Mirrors cloud-hypervisor's snapshot/restore flow: the core of
+//! `SystemState` (the `vms` map, per-VM capability sets, memory regions
+//! and vCPU counts, the `exits` queue, and each VM's `CPUState`/`Trapped`
+//! payload) can be serialized into a versioned snapshot blob and
+//! reconstructed deterministically on another process. Device buses,
+//! vsock mailboxes, breakpoints, configured address-space ceilings and
+//! unbacked hot-added regions are not part of the snapshot.
+//!
+//! Beyond a plain save/load, [`LiveMigration`] supports a "local" mode in
+//! which, instead of copying guest RAM, the sender hands the host file
+//! descriptors backing each mapped memory region to the receiver over a
+//! Unix domain socket together with the GPA→slot association, so the
+//! receiver re-maps the same pages via Hypervisor.framework without a
+//! bulk copy (cloud-hypervisor measured this dropping migration time from
+//! seconds to tens of milliseconds).
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::MemoryRegion;
+use crate::types::{Capability, ExitReason, SystemState, VMState, VMID};
+use crate::{Error, Result};
+
+/// Current snapshot format version
+///
+/// Bumped whenever the wire format changes; [`Migration::restore_system`]
+/// rejects snapshots from a version it doesn't understand.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Serializable snapshot of a single VM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VMSnapshot {
+    /// VMID at the time the snapshot was taken
+    pub vmid: VMID,
+    /// Lifecycle state, including any `CPUState`/`Trapped` payload
+    pub state: VMState,
+    /// Capability set, so capability soundness still holds on the destination
+    pub capabilities: Vec<Capability>,
+    /// Mapped memory regions
+    pub regions: Vec<MemoryRegion>,
+    /// Number of vCPUs the VM was created with
+    pub vcpu_count: u32,
+}
+
+/// Serializable snapshot of the entire system
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemSnapshot {
+    /// Snapshot format version
+    pub version: u32,
+    /// One entry per live VM
+    pub vms: Vec<VMSnapshot>,
+    /// Pending exits, keyed by the VMID recorded in `vms` and tagged with
+    /// the vCPU index that trapped
+    pub exits: Vec<(VMID, u32, ExitReason)>,
+}
+
+/// Snapshot/restore of the full system state
+pub struct Migration;
+
+impl Migration {
+    /// Capture a versioned snapshot of the entire system
+    pub fn snapshot_system(state: &SystemState) -> SystemSnapshot {
+        let vms = state
+            .vms
+            .iter()
+            .map(|(&vmid, vm_state)| VMSnapshot {
+                vmid,
+                state: vm_state.clone(),
+                capabilities: state
+                    .caps
+                    .get(&vmid)
+                    .map(|caps| caps.iter().copied().collect())
+                    .unwrap_or_default(),
+                regions: state.memory.get(&vmid).cloned().unwrap_or_default(),
+                vcpu_count: state.vcpu_counts.get(&vmid).copied().unwrap_or(1),
+            })
+            .collect();
+
+        SystemSnapshot {
+            version: SNAPSHOT_VERSION,
+            vms,
+            exits: state.exits.iter().cloned().collect(),
+        }
+    }
+
+    /// Reconstruct a `SystemState` from a snapshot
+    ///
+    /// Formal precondition: `snapshot.version` is a version this build
+    /// understands.
+    /// Formal postcondition: the restored state has the same VMIDs,
+    /// lifecycle states, capability sets, and memory regions as when the
+    /// snapshot was taken, so capability soundness holds immediately on
+    /// return.
+    pub fn restore_system(snapshot: &SystemSnapshot) -> Result<SystemState> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Error::MemoryError(format!(
+                "unsupported snapshot version {}",
+                snapshot.version
+            )));
+        }
+
+        let mut state = SystemState::new();
+
+        for vm in &snapshot.vms {
+            state.vms.insert(vm.vmid, vm.state.clone());
+            for cap in &vm.capabilities {
+                state.grant_capability(vm.vmid, *cap);
+            }
+            if !vm.regions.is_empty() {
+                state.memory.insert(vm.vmid, vm.regions.clone());
+            }
+            state.vcpu_counts.insert(vm.vmid, vm.vcpu_count);
+            state.reserve_vmid(vm.vmid);
+        }
+
+        state.exits = snapshot.exits.iter().cloned().collect::<VecDeque<_>>();
+
+        Ok(state)
+    }
+
+    /// Serialize a snapshot into a versioned blob
+    pub fn serialize_snapshot(snapshot: &SystemSnapshot) -> Result<Vec<u8>> {
+        serde_json::to_vec(snapshot)
+            .map_err(|e| Error::MemoryError(format!("snapshot serialization failed: {e}")))
+    }
+
+    /// Deserialize a versioned blob back into a snapshot
+    pub fn deserialize_snapshot(bytes: &[u8]) -> Result<SystemSnapshot> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| Error::MemoryError(format!("snapshot deserialization failed: {e}")))
+    }
+}
+
+/// A GPA→slot association for one guest memory region whose backing host
+/// fd is handed off during local live migration instead of being copied.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MemorySlotHandoff {
+    /// Region being handed off
+    pub region: MemoryRegion,
+    /// HVF memory slot the region is mapped at on the sender
+    pub slot: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MigrationPayload {
+    snapshot: SystemSnapshot,
+    handoffs: Vec<MemorySlotHandoff>,
+}
+
+/// Local live migration of a single VM
+///
+/// # Trust model
+///
+/// As with the [`crate::hvf`] module, the host mechanism for passing file
+/// descriptors across a Unix domain socket (`SCM_RIGHTS`) is assumed
+/// correct and not modeled here. [`LiveMigration::send_local`] and
+/// [`LiveMigration::receive_local`] exchange the VM snapshot plus the
+/// GPA→slot metadata; a real implementation attaches each region's host
+/// fd as ancillary data on the same socket, and the receiver re-maps it
+/// through `HVF::map_memory` at the recorded slot instead of copying
+/// guest RAM.
+pub struct LiveMigration;
+
+impl LiveMigration {
+    /// Sender side of a local live migration
+    ///
+    /// Sends the snapshot for `vmid` followed by the slot handoff list so
+    /// the receiver knows which GPA ranges to re-map.
+    pub fn send_local(
+        state: &SystemState,
+        vmid: VMID,
+        handoffs: &[MemorySlotHandoff],
+        socket: &mut UnixStream,
+    ) -> Result<()> {
+        let snapshot = Self::snapshot_one(state, vmid)?;
+        let payload = MigrationPayload {
+            snapshot,
+            handoffs: handoffs.to_vec(),
+        };
+
+        let bytes = serde_json::to_vec(&payload)
+            .map_err(|e| Error::MemoryError(format!("migration payload encode failed: {e}")))?;
+
+        let len = (bytes.len() as u64).to_le_bytes();
+        socket
+            .write_all(&len)
+            .and_then(|_| socket.write_all(&bytes))
+            .map_err(|e| Error::MemoryError(format!("migration send failed: {e}")))
+    }
+
+    /// Receiver side of a local live migration
+    ///
+    /// Reads back the snapshot and slot handoffs sent by
+    /// [`send_local`](Self::send_local), restores the VM under a fresh
+    /// VMID, and returns the handoffs so the caller can re-map each
+    /// region's fd through `HVF::map_memory` at its recorded slot.
+    pub fn receive_local(
+        socket: &mut UnixStream,
+    ) -> Result<(SystemState, VMID, Vec<MemorySlotHandoff>)> {
+        let mut len_buf = [0u8; 8];
+        socket
+            .read_exact(&mut len_buf)
+            .map_err(|e| Error::MemoryError(format!("migration recv failed: {e}")))?;
+        let len = u64::from_le_bytes(len_buf) as usize;
+
+        let mut bytes = vec![0u8; len];
+        socket
+            .read_exact(&mut bytes)
+            .map_err(|e| Error::MemoryError(format!("migration recv failed: {e}")))?;
+
+        let payload: MigrationPayload = serde_json::from_slice(&bytes)
+            .map_err(|e| Error::MemoryError(format!("migration payload decode failed: {e}")))?;
+
+        let old_vmid = payload
+            .snapshot
+            .vms
+            .first()
+            .map(|vm| vm.vmid)
+            .ok_or_else(|| Error::MemoryError("empty migration snapshot".to_string()))?;
+
+        let mut state = Migration::restore_system(&payload.snapshot)?;
+
+        // Restore allocates a fresh VMID rather than reusing the
+        // serialized one, so re-key the single restored VM.
+        let new_vmid = state.allocate_vmid();
+        if let Some(vm_state) = state.vms.remove(&old_vmid) {
+            state.vms.insert(new_vmid, vm_state);
+        }
+        if let Some(caps) = state.caps.remove(&old_vmid) {
+            state.caps.insert(new_vmid, caps);
+        }
+        if let Some(regions) = state.memory.remove(&old_vmid) {
+            state.memory.insert(new_vmid, regions);
+        }
+        if let Some(vcpu_count) = state.vcpu_counts.remove(&old_vmid) {
+            state.vcpu_counts.insert(new_vmid, vcpu_count);
+        }
+
+        Ok((state, new_vmid, payload.handoffs))
+    }
+
+    fn snapshot_one(state: &SystemState, vmid: VMID) -> Result<SystemSnapshot> {
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+
+        let vm = VMSnapshot {
+            vmid,
+            state: vm_state.clone(),
+            capabilities: state
+                .caps
+                .get(&vmid)
+                .map(|caps| caps.iter().copied().collect())
+                .unwrap_or_default(),
+            regions: state.memory.get(&vmid).cloned().unwrap_or_default(),
+            vcpu_count: state.vcpu_counts.get(&vmid).copied().unwrap_or(1),
+        };
+
+        Ok(SystemSnapshot {
+            version: SNAPSHOT_VERSION,
+            vms: vec![vm],
+            exits: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryManager;
+    use crate::types::CPUState;
+    use crate::vm::VMManager;
+
+    #[test]
+    fn test_snapshot_restore_round_trip() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        MemoryManager::map_region(
+            &mut state,
+            vmid,
+            MemoryRegion {
+                gpa: crate::types::GPA(0x1000),
+                size: 0x1000,
+            },
+        )
+        .unwrap();
+
+        let snapshot = Migration::snapshot_system(&state);
+        let restored = Migration::restore_system(&snapshot).unwrap();
+
+        assert!(matches!(restored.vms.get(&vmid), Some(VMState::Runnable(_))));
+        assert!(restored.has_capability(vmid, Capability::Execute));
+        assert_eq!(MemoryManager::get_regions(&restored, vmid).len(), 1);
+    }
+
+    #[test]
+    fn test_serialize_deserialize_round_trip() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
+        let snapshot = Migration::snapshot_system(&state);
+        let bytes = Migration::serialize_snapshot(&snapshot).unwrap();
+        let decoded = Migration::deserialize_snapshot(&bytes).unwrap();
+
+        let restored = Migration::restore_system(&decoded).unwrap();
+        assert!(restored.vms.contains_key(&vmid));
+    }
+
+    #[test]
+    fn test_restore_rejects_unknown_version() {
+        let snapshot = SystemSnapshot {
+            version: SNAPSHOT_VERSION + 1,
+            vms: Vec::new(),
+            exits: Vec::new(),
+        };
+
+        assert!(Migration::restore_system(&snapshot).is_err());
+    }
+
+    #[test]
+    fn test_live_migration_local_round_trip() {
+        let mut state = SystemState::new();
+        let _other_vm = VMManager::create_vm(&mut state, 1).unwrap();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        let region = MemoryRegion {
+            gpa: crate::types::GPA(0x2000),
+            size: 0x1000,
+        };
+        MemoryManager::map_region(&mut state, vmid, region).unwrap();
+
+        let handoffs = vec![MemorySlotHandoff { region, slot: 0 }];
+
+        let (mut sender, mut receiver) = UnixStream::pair().unwrap();
+        LiveMigration::send_local(&state, vmid, &handoffs, &mut sender).unwrap();
+        let (restored, new_vmid, recv_handoffs) =
+            LiveMigration::receive_local(&mut receiver).unwrap();
+
+        assert_ne!(new_vmid, vmid);
+        assert!(restored.has_capability(new_vmid, Capability::Execute));
+        assert_eq!(MemoryManager::get_regions(&restored, new_vmid).len(), 1);
+        assert_eq!(recv_handoffs.len(), 1);
+        assert_eq!(recv_handoffs[0].region, region);
+    }
+
+    #[test]
+    fn test_live_migration_reallocates_vmid_zero() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        assert_eq!(vmid, VMID(0));
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+
+        let (mut sender, mut receiver) = UnixStream::pair().unwrap();
+        LiveMigration::send_local(&state, vmid, &[], &mut sender).unwrap();
+        let (restored, new_vmid, _) = LiveMigration::receive_local(&mut receiver).unwrap();
+
+        assert_ne!(new_vmid, vmid);
+        assert!(restored.has_capability(new_vmid, Capability::Execute));
+    }
+
+    #[test]
+    fn test_live_migration_preserves_smp_vcpu_count() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 2).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            Capability::Snapshot,
+        )
+        .unwrap();
+        VMManager::initialize_vm(
+            &mut state,
+            vmid,
+            vec![CPUState::default(), CPUState::default()],
+        )
+        .unwrap();
+        VMManager::pause_vm(&mut state, vmid).unwrap();
+
+        let (mut sender, mut receiver) = UnixStream::pair().unwrap();
+        LiveMigration::send_local(&state, vmid, &[], &mut sender).unwrap();
+        let (mut restored, new_vmid, _) = LiveMigration::receive_local(&mut receiver).unwrap();
+
+        assert_ne!(new_vmid, vmid);
+        VMManager::resume_from_pause(
+            &mut restored,
+            new_vmid,
+            vec![CPUState::default(), CPUState::default()],
+        )
+        .unwrap();
+        assert!(matches!(
+            restored.vms.get(&new_vmid),
+            Some(VMState::Runnable(cpus)) if cpus.len() == 2
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+//! Per-thread seccomp sandboxing for vCPU and VMM threads
+//!
+//! Mirrors cloud-hypervisor's `seccomp_filters` module (`Thread`,
+//! `get_seccomp_filter`): each OS thread touching host syscalls installs
+//! an allow-listed filter before doing privileged work. Because µH-iOS
+//! targets Apple's Hypervisor.framework rather than Linux's KVM, seccomp
+//! itself has no iOS/macOS equivalent (Apple's nearest primitive is the
+//! sandbox profile / Seatbelt, installed per-process rather than
+//! per-thread) — this module models the filter data and installation
+//! call so the allow-list stays auditable and the intended insertion
+//! point ([`crate::hvf::HVF::run_vcpu`]) is documented, without linking a
+//! Linux-only syscall filter into an HVF host process.
+
+use crate::{Error, Result};
+
+/// Category of OS thread a filter is installed for
+///
+/// Mirrors cloud-hypervisor's `Thread` enum: each category gets its own
+/// allow-list, since a vCPU thread and the API/signal-handling threads
+/// touch disjoint sets of syscalls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadCategory {
+    /// The per-vCPU thread driving [`crate::hvf::HVF::run_vcpu`]'s run loop
+    Vcpu,
+    /// A thread handling an async signal (e.g. a vCPU kick)
+    Signal,
+    /// A thread serving the management API
+    Api,
+}
+
+/// Disposition for a syscall that matches no allow-listed rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeccompAction {
+    /// Permit the syscall
+    Allow,
+    /// Permit the syscall but log it, for auditing during development
+    Log,
+    /// Deliver `SIGSYS` to the calling thread
+    Trap,
+    /// Terminate the process immediately
+    Kill,
+}
+
+/// A condition on a single syscall argument
+///
+/// Modeled after `seccompiler::SeccompRule`'s argument comparisons: a
+/// filter entry matches only when every rule for that syscall holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeccompRule {
+    /// Index of the argument being compared (0-5)
+    pub arg_index: u8,
+    /// Value the argument is compared against
+    pub value: u64,
+}
+
+/// A seccomp filter installed for one OS thread: the thread's category,
+/// its data-driven syscall allow-list, and the fallback action for
+/// anything not on it
+pub struct SeccompFilter {
+    category: ThreadCategory,
+    mismatch_action: SeccompAction,
+    allowed_syscalls: Vec<(i64, Vec<SeccompRule>)>,
+}
+
+impl SeccompFilter {
+    /// Category this filter was installed for
+    pub fn category(&self) -> ThreadCategory {
+        self.category
+    }
+
+    /// Action taken for syscalls that match no allow-list entry
+    pub fn mismatch_action(&self) -> SeccompAction {
+        self.mismatch_action
+    }
+
+    /// The data-driven allow-list: syscall number paired with the
+    /// argument rules that must all hold for it to be permitted
+    pub fn allowed_syscalls(&self) -> &[(i64, Vec<SeccompRule>)] {
+        &self.allowed_syscalls
+    }
+
+    /// Whether `syscall_nr` is present in the allow-list, ignoring its
+    /// argument rules (a full match additionally needs every
+    /// [`SeccompRule`] for that entry to hold)
+    pub fn allows_syscall(&self, syscall_nr: i64) -> bool {
+        self.allowed_syscalls.iter().any(|(nr, _)| *nr == syscall_nr)
+    }
+}
+
+// Stand-in Linux syscall numbers (x86_64): µH-iOS runs on Apple
+// platforms where these are meaningless, but a data-driven allow-list
+// needs concrete `i64` entries to stay auditable against
+// cloud-hypervisor's Linux-hosted filters.
+const SYS_READ: i64 = 0;
+const SYS_WRITE: i64 = 1;
+const SYS_ACCEPT: i64 = 43;
+const SYS_RT_SIGACTION: i64 = 13;
+const SYS_RT_SIGRETURN: i64 = 15;
+const SYS_FUTEX: i64 = 202;
+const SYS_CLOCK_GETTIME: i64 = 228;
+
+/// Build the data-driven allow-list for `category`
+///
+/// In real implementation (on a Linux KVM backend) this is the auditable
+/// table passed to `seccompiler::backend::SeccompFilter`; here it
+/// enumerates the syscalls each thread category is expected to need.
+fn allow_list_for(category: ThreadCategory) -> Vec<(i64, Vec<SeccompRule>)> {
+    match category {
+        // hv_vcpu_run and friends are Hypervisor.framework calls, not
+        // raw syscalls, but the vCPU thread still parks on a Condvar
+        // between exits (cpu.rs's run loop), which needs futex and
+        // clock syscalls underneath it.
+        ThreadCategory::Vcpu => vec![(SYS_FUTEX, vec![]), (SYS_CLOCK_GETTIME, vec![])],
+        ThreadCategory::Signal => vec![(SYS_RT_SIGACTION, vec![]), (SYS_RT_SIGRETURN, vec![])],
+        ThreadCategory::Api => vec![(SYS_READ, vec![]), (SYS_WRITE, vec![]), (SYS_ACCEPT, vec![])],
+    }
+}
+
+/// Install a seccomp filter for the calling thread
+///
+/// Formal precondition: called once per thread, before it performs any
+/// privileged work — in particular, before a vCPU thread's first call to
+/// [`crate::hvf::HVF::run_vcpu`], which is documented to execute under
+/// the `Vcpu` filter.
+///
+/// In real implementation, compiles the allow-list to BPF via
+/// `seccompiler::backend::SeccompFilter::try_into()` and installs it with
+/// `prctl(PR_SET_SECCOMP, ...)`, one filter per OS thread. µH-iOS has no
+/// Linux-syscall surface to filter, so this is modeled as a stub that
+/// records the installed filter rather than programming the kernel.
+pub fn apply_filter(category: ThreadCategory, action: SeccompAction) -> Result<SeccompFilter> {
+    let allowed_syscalls = allow_list_for(category);
+    if allowed_syscalls.is_empty() {
+        return Err(Error::HVFError(format!(
+            "no seccomp allow-list defined for {category:?}"
+        )));
+    }
+
+    Ok(SeccompFilter {
+        category,
+        mismatch_action: action,
+        allowed_syscalls,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_filter_records_category_and_action() {
+        let filter = apply_filter(ThreadCategory::Vcpu, SeccompAction::Trap).unwrap();
+        assert_eq!(filter.category(), ThreadCategory::Vcpu);
+        assert_eq!(filter.mismatch_action(), SeccompAction::Trap);
+    }
+
+    #[test]
+    fn test_vcpu_filter_allows_futex_not_accept() {
+        let filter = apply_filter(ThreadCategory::Vcpu, SeccompAction::Kill).unwrap();
+        assert!(filter.allows_syscall(SYS_FUTEX));
+        assert!(!filter.allows_syscall(SYS_ACCEPT));
+    }
+
+    #[test]
+    fn test_each_category_has_a_disjoint_allow_list() {
+        let vcpu = apply_filter(ThreadCategory::Vcpu, SeccompAction::Trap).unwrap();
+        let api = apply_filter(ThreadCategory::Api, SeccompAction::Trap).unwrap();
+        assert!(!vcpu.allows_syscall(SYS_ACCEPT));
+        assert!(api.allows_syscall(SYS_ACCEPT));
+
+        let signal = apply_filter(ThreadCategory::Signal, SeccompAction::Trap).unwrap();
+        let filters = [&vcpu, &signal, &api];
+        for (i, a) in filters.iter().enumerate() {
+            for (j, b) in filters.iter().enumerate() {
+                if i != j {
+                    for (nr, _) in a.allowed_syscalls() {
+                        assert!(!b.allows_syscall(*nr));
+                    }
+                }
+            }
+        }
+    }
+}
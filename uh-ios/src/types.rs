@@ -0,0 +1,222 @@
+//! Core type definitions and system state
+//!
+//! This module defines the fundamental types shared across µH-iOS and the
+//! `SystemState` that anchors the formal invariants: memory
+//! non-interference, capability soundness, deterministic exit handling,
+//! and totality.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+/// Unique identifier for a virtual machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct VMID(pub u32);
+
+impl std::fmt::Display for VMID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Guest physical address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GPA(pub u64);
+
+/// Capabilities that gate privileged operations
+///
+/// Formal: an action may occur if and only if the executing VM possesses
+/// the corresponding capability prior to execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Capability {
+    /// Permission to run the VM
+    Execute,
+    /// Permission to map/unmap guest memory
+    MapMemory,
+    /// Permission to handle VM exits
+    HandleExit,
+    /// Permission to halt the VM
+    Halt,
+    /// Permission to attach a GDB Remote Serial Protocol debug stub
+    Debug,
+    /// Permission to register an emulated MMIO device
+    MmioAccess,
+    /// Permission to send vsock-style messages to the named VM's mailbox
+    SendTo(VMID),
+    /// Permission to have the named VM deposit vsock-style messages into
+    /// this VM's mailbox
+    ReceiveFrom(VMID),
+    /// Permission to hot-add or hot-remove guest memory regions at runtime
+    HotplugMemory,
+    /// Permission to pause/resume a VM and to snapshot/restore it
+    Snapshot,
+}
+
+/// CPU register file for a single vCPU
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CPUState {
+    /// General purpose registers (x0-x30)
+    pub gpr: [u64; 31],
+    /// Program counter
+    pub pc: u64,
+    /// Stack pointer
+    pub sp: u64,
+    /// Processor state (PSTATE)
+    pub pstate: u64,
+}
+
+/// Reason a VM exited back to the hypervisor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExitReason {
+    /// Guest issued a hypercall
+    Hypercall {
+        /// Hypercall number
+        nr: u64,
+        /// Hypercall arguments
+        args: [u64; 6],
+    },
+    /// Guest data access faulted
+    MemoryFault {
+        /// Faulting guest physical address
+        gpa: GPA,
+        /// Whether the access was a write
+        write: bool,
+    },
+    /// Guest instruction fetch faulted
+    InstructionAbort {
+        /// Faulting guest physical address
+        gpa: GPA,
+    },
+    /// Guest accessed a trapped system register
+    SystemRegister {
+        /// Encoded system register id
+        reg: u32,
+        /// Whether the access was a write
+        write: bool,
+    },
+    /// Guest executed WFI (Wait For Interrupt)
+    WFI,
+    /// Guest raised an exception
+    Exception {
+        /// Exception vector number
+        vector: u32,
+    },
+    /// VM run was cancelled by the host
+    Cancelled,
+    /// Guest PC matched an address registered with [`crate::debugger::Debugger::set_breakpoint`]
+    Breakpoint,
+}
+
+/// Lifecycle state of a virtual machine
+///
+/// `Runnable`/`Trapped`/`Paused`/`Debugging` each carry one [`CPUState`] per
+/// vCPU, indexed identically to the vCPU indices passed to
+/// [`crate::vm::VMManager::trap_vm`]/`resume_vm`, so an SMP guest's
+/// register files travel together through every transition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum VMState {
+    /// VM has been created but has no CPU state yet
+    Created,
+    /// VM is runnable with the given per-vCPU CPU states
+    Runnable(Vec<CPUState>),
+    /// VM has trapped to the host with the exit reason and the per-vCPU
+    /// CPU states at the time of exit
+    Trapped(ExitReason, Vec<CPUState>),
+    /// VM is paused with the given per-vCPU CPU states, e.g. for
+    /// snapshotting
+    Paused(Vec<CPUState>),
+    /// VM is suspended for interactive debugging with the given per-vCPU
+    /// CPU states, reachable from `Trapped`/`Paused` via
+    /// [`crate::vm::VMManager::enter_debugging`]
+    Debugging(Vec<CPUState>),
+    /// VM has halted permanently (terminal state)
+    Halted,
+}
+
+/// Global system state
+///
+/// Anchors the formal invariants: each VM's memory regions, capability
+/// set, and pending exits are tracked here.
+pub struct SystemState {
+    /// Per-VM lifecycle state
+    pub vms: HashMap<VMID, VMState>,
+    /// Per-VM capability sets
+    pub caps: HashMap<VMID, HashSet<Capability>>,
+    /// Per-VM mapped memory regions
+    pub memory: HashMap<VMID, Vec<crate::memory::MemoryRegion>>,
+    /// Per-VM emulated MMIO device bus
+    pub devices: HashMap<VMID, crate::device::DeviceBus>,
+    /// Per-VM bounded vsock-style mailbox of inbound message bytes
+    pub mailboxes: HashMap<VMID, VecDeque<u8>>,
+    /// Base GPAs of hot-added regions not yet backed by a host mapping
+    pub unbacked_regions: HashMap<VMID, Vec<GPA>>,
+    /// Number of vCPUs each VM was created with
+    pub vcpu_counts: HashMap<VMID, u32>,
+    /// Per-VM set of guest addresses registered as breakpoints
+    pub breakpoints: HashMap<VMID, HashSet<u64>>,
+    /// Per-VM configured guest physical-address width, in bits, as set by
+    /// [`crate::vm::VMManager::configure_address_space`]. Consulted by
+    /// [`crate::memory::MemoryManager::map_region`] to reject regions that
+    /// would fall outside the host-backed address space; a VM with no
+    /// entry here has no configured ceiling.
+    pub addr_space_bits: HashMap<VMID, u8>,
+    /// Pending VM exits awaiting processing, tagged with the vCPU index
+    /// that trapped
+    pub exits: VecDeque<(VMID, u32, ExitReason)>,
+    next_vmid: u32,
+}
+
+impl SystemState {
+    /// Create a new, empty system state
+    pub fn new() -> Self {
+        SystemState {
+            vms: HashMap::new(),
+            caps: HashMap::new(),
+            memory: HashMap::new(),
+            devices: HashMap::new(),
+            mailboxes: HashMap::new(),
+            unbacked_regions: HashMap::new(),
+            vcpu_counts: HashMap::new(),
+            breakpoints: HashMap::new(),
+            addr_space_bits: HashMap::new(),
+            exits: VecDeque::new(),
+            next_vmid: 0,
+        }
+    }
+
+    /// Allocate a fresh, unused VMID
+    pub fn allocate_vmid(&mut self) -> VMID {
+        let vmid = VMID(self.next_vmid);
+        self.next_vmid += 1;
+        vmid
+    }
+
+    /// Ensure future [`allocate_vmid`](Self::allocate_vmid) calls return ids
+    /// past `vmid`
+    ///
+    /// Used when reconstructing a `SystemState` from a snapshot, so a
+    /// freshly allocated VMID can never collide with one carried over from
+    /// the snapshot.
+    pub(crate) fn reserve_vmid(&mut self, vmid: VMID) {
+        self.next_vmid = self.next_vmid.max(vmid.0 + 1);
+    }
+
+    /// Check whether a VM possesses a capability
+    pub fn has_capability(&self, vmid: VMID, cap: Capability) -> bool {
+        self.caps
+            .get(&vmid)
+            .map(|caps| caps.contains(&cap))
+            .unwrap_or(false)
+    }
+
+    /// Grant a capability to a VM
+    pub fn grant_capability(&mut self, vmid: VMID, cap: Capability) {
+        self.caps.entry(vmid).or_default().insert(cap);
+    }
+}
+
+impl Default for SystemState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -4,120 +4,234 @@
 //! All state transitions are explicit and deterministic, maintaining system invariants.
 
 use crate::{Error, Result};
+use crate::hvf::HVF;
+use crate::migration::VMSnapshot;
 use crate::types::{SystemState, VMState, VMID, CPUState, Capability};
 
 /// VM lifecycle operations
 pub struct VMManager;
 
 impl VMManager {
-    /// Create a new VM
+    /// Create a new VM with `vcpu_count` vCPUs
     ///
-    /// Formal precondition: None
+    /// Formal precondition: `vcpu_count` is between 1 and
+    /// `HVF::get_system_info().max_vcpus` inclusive
     /// Formal postcondition: VM exists in Created state with Execute capability
     ///
     /// State transition: ∅ → Created
-    pub fn create_vm(state: &mut SystemState) -> Result<VMID> {
+    pub fn create_vm(state: &mut SystemState, vcpu_count: u32) -> Result<VMID> {
+        let max_vcpus = HVF::get_system_info()?.max_vcpus;
+        if vcpu_count == 0 || vcpu_count > max_vcpus {
+            return Err(Error::HVFError(format!(
+                "vCPU count {vcpu_count} outside supported range 1..={max_vcpus}"
+            )));
+        }
+
         let vmid = state.allocate_vmid();
-        
+
         // Initialize VM in Created state
         state.vms.insert(vmid, VMState::Created);
-        
+        state.vcpu_counts.insert(vmid, vcpu_count);
+
         // Grant basic capabilities
         state.grant_capability(vmid, Capability::Execute);
         state.grant_capability(vmid, Capability::MapMemory);
         state.grant_capability(vmid, Capability::HandleExit);
         state.grant_capability(vmid, Capability::Halt);
-        
+
         Ok(vmid)
     }
-    
-    /// Initialize VM with CPU state, making it runnable
+
+    /// Initialize VM with one CPU state per vCPU, making it runnable
     ///
-    /// Formal precondition: VM exists in Created state, possesses Execute capability
-    /// Formal postcondition: VM is in Runnable state with provided CPU state
+    /// Formal precondition: VM exists in Created state, possesses Execute
+    /// capability, and `cpu_states.len()` matches the vCPU count it was
+    /// created with
+    /// Formal postcondition: VM is in Runnable state with the provided
+    /// per-vCPU CPU states
     ///
-    /// State transition: Created → Runnable(CPUState)
+    /// State transition: Created → Runnable(Vec<CPUState>)
     pub fn initialize_vm(
         state: &mut SystemState,
         vmid: VMID,
-        cpu_state: CPUState,
+        cpu_states: Vec<CPUState>,
     ) -> Result<()> {
         // Verify VM exists
         let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
-        
+
         // Verify VM is in Created state
         if !matches!(vm_state, VMState::Created) {
             return Err(Error::InvalidVMState(vmid));
         }
-        
+
         // Verify Execute capability
         if !state.has_capability(vmid, Capability::Execute) {
             return Err(Error::CapabilityError(
                 "Execute capability required".to_string(),
             ));
         }
-        
+
+        Self::check_vcpu_count(state, vmid, cpu_states.len())?;
+
         // Transition to Runnable state
-        state.vms.insert(vmid, VMState::Runnable(cpu_state));
-        
+        state.vms.insert(vmid, VMState::Runnable(cpu_states));
+
         Ok(())
     }
-    
-    /// Handle VM trap (exit)
+
+    /// Handle a trap (exit) from the vCPU at index `vcpu`
     ///
-    /// Formal precondition: VM exists in Runnable state
-    /// Formal postcondition: VM is in Trapped state with exit reason and current CPU state
+    /// Formal precondition: VM exists in Runnable state, `vcpu` is a valid
+    /// vCPU index for it
+    /// Formal postcondition: VM is in Trapped state with the exit reason
+    /// and `vcpu`'s updated CPU state. If `cpu_state.pc` matches an
+    /// address registered via [`crate::debugger::Debugger::set_breakpoint`]
+    /// for `vmid`, the recorded exit reason is `ExitReason::Breakpoint`
+    /// regardless of what HVF reported.
     ///
-    /// State transition: Runnable(CPUState) → Trapped(ExitReason, CPUState)
+    /// State transition: Runnable(cpu_states) → Trapped(ExitReason, cpu_states)
     pub fn trap_vm(
         state: &mut SystemState,
         vmid: VMID,
+        vcpu: u32,
         exit_reason: crate::types::ExitReason,
         cpu_state: CPUState,
     ) -> Result<()> {
-        // Verify VM exists
-        if !state.vms.contains_key(&vmid) {
-            return Err(Error::VMNotFound(vmid));
-        }
-        
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+
+        let mut cpu_states = match vm_state {
+            VMState::Runnable(cpu_states) => cpu_states.clone(),
+            _ => return Err(Error::InvalidVMState(vmid)),
+        };
+
+        let exit_reason = if state
+            .breakpoints
+            .get(&vmid)
+            .is_some_and(|bps| bps.contains(&cpu_state.pc))
+        {
+            crate::types::ExitReason::Breakpoint
+        } else {
+            exit_reason
+        };
+
+        let slot = cpu_states
+            .get_mut(vcpu as usize)
+            .ok_or(Error::InvalidVMState(vmid))?;
+        *slot = cpu_state;
+
         // Transition to Trapped state
-        state.vms.insert(vmid, VMState::Trapped(exit_reason.clone(), cpu_state));
-        
-        // Enqueue exit for processing
-        state.exits.push_back((vmid, exit_reason));
-        
+        state.vms.insert(vmid, VMState::Trapped(exit_reason.clone(), cpu_states));
+
+        // Enqueue exit for processing, tagged with the trapping vCPU
+        state.exits.push_back((vmid, vcpu, exit_reason));
+
         Ok(())
     }
-    
-    /// Resume VM from trapped state
+
+    /// Resume the vCPU at index `vcpu` from trapped (or debugging) state
     ///
-    /// Formal precondition: VM exists in Trapped state, possesses Execute capability
-    /// Formal postcondition: VM is in Runnable state with updated CPU state
+    /// Formal precondition: VM exists in Trapped or Debugging state,
+    /// possesses Execute capability, `vcpu` is a valid vCPU index for it
+    /// Formal postcondition: VM is in Runnable state with `vcpu`'s updated
+    /// CPU state
     ///
-    /// State transition: Trapped(_, CPUState) → Runnable(CPUState)
+    /// State transition: Trapped(_, cpu_states) | Debugging(cpu_states) → Runnable(cpu_states)
     pub fn resume_vm(
         state: &mut SystemState,
         vmid: VMID,
+        vcpu: u32,
         cpu_state: CPUState,
     ) -> Result<()> {
-        // Verify VM exists
         let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
-        
-        // Verify VM is in Trapped state
-        if !matches!(vm_state, VMState::Trapped(_, _)) {
-            return Err(Error::InvalidVMState(vmid));
-        }
-        
+
+        let mut cpu_states = match vm_state {
+            VMState::Trapped(_, cpu_states) | VMState::Debugging(cpu_states) => cpu_states.clone(),
+            _ => return Err(Error::InvalidVMState(vmid)),
+        };
+
         // Verify Execute capability
         if !state.has_capability(vmid, Capability::Execute) {
             return Err(Error::CapabilityError(
                 "Execute capability required".to_string(),
             ));
         }
-        
+
+        let slot = cpu_states
+            .get_mut(vcpu as usize)
+            .ok_or(Error::InvalidVMState(vmid))?;
+        *slot = cpu_state;
+
         // Transition to Runnable state
-        state.vms.insert(vmid, VMState::Runnable(cpu_state));
-        
+        state.vms.insert(vmid, VMState::Runnable(cpu_states));
+
+        Ok(())
+    }
+
+    /// Enter interactive debugging for a trapped or paused VM
+    ///
+    /// Formal precondition: VM exists in Trapped or Paused state, possesses
+    /// Debug capability
+    /// Formal postcondition: VM is in Debugging state with the same
+    /// per-vCPU CPU states, so registers stay stable while
+    /// [`crate::debugger::Debugger`] inspects or steps them
+    ///
+    /// State transition: Trapped(_, cpu_states) | Paused(cpu_states) → Debugging(cpu_states)
+    pub fn enter_debugging(state: &mut SystemState, vmid: VMID) -> Result<()> {
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+
+        let cpu_states = match vm_state {
+            VMState::Trapped(_, cpu_states) | VMState::Paused(cpu_states) => cpu_states.clone(),
+            _ => return Err(Error::InvalidVMState(vmid)),
+        };
+
+        if !state.has_capability(vmid, Capability::Debug) {
+            return Err(Error::CapabilityError(
+                "Debug capability required".to_string(),
+            ));
+        }
+
+        state.vms.insert(vmid, VMState::Debugging(cpu_states));
+
+        Ok(())
+    }
+
+    /// Configure the guest physical address space ceiling for a VM
+    ///
+    /// Mirrors cloud-hypervisor's `get_host_cpu_phys_bits`: clamps a
+    /// caller-requested physical-address width to what
+    /// `HVF::get_system_info` reports the host supports, rather than
+    /// trusting the caller's request outright.
+    ///
+    /// Formal precondition: VM exists
+    /// Formal postcondition: the VM's configured ceiling is
+    /// `min(requested_bits.unwrap_or(host_bits), host_bits)`; subsequent
+    /// [`crate::memory::MemoryManager::map_region`] calls for this VM
+    /// reject any region whose end address exceeds `1 << ceiling`.
+    ///
+    /// Returns the effective bit width that was configured.
+    pub fn configure_address_space(
+        state: &mut SystemState,
+        vmid: VMID,
+        requested_bits: Option<u8>,
+    ) -> Result<u8> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+
+        let host_bits = HVF::get_system_info()?.phys_addr_bits;
+        let effective_bits = requested_bits.map_or(host_bits, |requested| requested.min(host_bits));
+
+        state.addr_space_bits.insert(vmid, effective_bits);
+
+        Ok(effective_bits)
+    }
+
+    /// Verify that `len` matches the vCPU count `vmid` was created with
+    fn check_vcpu_count(state: &SystemState, vmid: VMID, len: usize) -> Result<()> {
+        let expected = *state.vcpu_counts.get(&vmid).unwrap_or(&1) as usize;
+        if len != expected {
+            return Err(Error::InvalidVMState(vmid));
+        }
         Ok(())
     }
     
@@ -150,6 +264,144 @@ impl VMManager {
     pub fn get_vm_state(state: &SystemState, vmid: VMID) -> Result<&VMState> {
         state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))
     }
+
+    /// Pause a running VM, e.g. in preparation for a snapshot
+    ///
+    /// Formal precondition: VM exists in Runnable state, possesses
+    /// Snapshot capability
+    /// Formal postcondition: VM is in Paused state with the per-vCPU CPU
+    /// states it was running with
+    ///
+    /// State transition: Runnable(cpu_states) → Paused(cpu_states)
+    pub fn pause_vm(state: &mut SystemState, vmid: VMID) -> Result<()> {
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+
+        let cpu_states = match vm_state {
+            VMState::Runnable(cpu_states) => cpu_states.clone(),
+            _ => return Err(Error::InvalidVMState(vmid)),
+        };
+
+        if !state.has_capability(vmid, Capability::Snapshot) {
+            return Err(Error::CapabilityError(
+                "Snapshot capability required".to_string(),
+            ));
+        }
+
+        state.vms.insert(vmid, VMState::Paused(cpu_states));
+
+        Ok(())
+    }
+
+    /// Resume a paused VM
+    ///
+    /// Formal precondition: VM exists in Paused state, possesses Snapshot
+    /// capability, and `cpu_states.len()` matches the vCPU count it was
+    /// created with
+    /// Formal postcondition: VM is in Runnable state with updated per-vCPU
+    /// CPU states
+    ///
+    /// State transition: Paused(_) → Runnable(cpu_states)
+    pub fn resume_from_pause(
+        state: &mut SystemState,
+        vmid: VMID,
+        cpu_states: Vec<CPUState>,
+    ) -> Result<()> {
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+
+        if !matches!(vm_state, VMState::Paused(_)) {
+            return Err(Error::InvalidVMState(vmid));
+        }
+
+        if !state.has_capability(vmid, Capability::Snapshot) {
+            return Err(Error::CapabilityError(
+                "Snapshot capability required".to_string(),
+            ));
+        }
+
+        Self::check_vcpu_count(state, vmid, cpu_states.len())?;
+
+        state.vms.insert(vmid, VMState::Runnable(cpu_states));
+
+        Ok(())
+    }
+
+    /// Capture a snapshot of a single VM
+    ///
+    /// Formal precondition: VM exists in Paused or Trapped state (so its
+    /// CPU state is consistent), possesses Snapshot capability
+    pub fn snapshot_vm(state: &SystemState, vmid: VMID) -> Result<VMSnapshot> {
+        let vm_state = state.vms.get(&vmid).ok_or(Error::VMNotFound(vmid))?;
+
+        if !state.has_capability(vmid, Capability::Snapshot) {
+            return Err(Error::CapabilityError(
+                "Snapshot capability required".to_string(),
+            ));
+        }
+
+        if !matches!(vm_state, VMState::Paused(_) | VMState::Trapped(_, _)) {
+            return Err(Error::InvalidVMState(vmid));
+        }
+
+        Ok(VMSnapshot {
+            vmid,
+            state: vm_state.clone(),
+            capabilities: state
+                .caps
+                .get(&vmid)
+                .map(|caps| caps.iter().copied().collect())
+                .unwrap_or_default(),
+            regions: state.memory.get(&vmid).cloned().unwrap_or_default(),
+            vcpu_count: state.vcpu_counts.get(&vmid).copied().unwrap_or(1),
+        })
+    }
+
+    /// Restore a VM from a snapshot under a fresh VMID
+    ///
+    /// Formal precondition: `snapshot.state` is Paused or Trapped, so a
+    /// consistent CPU state exists to replay, and every snapshot region is
+    /// disjoint from every region already mapped in `state`.
+    /// Formal postcondition: a new VM exists with the snapshot's
+    /// capability set, memory regions, and lifecycle state, with its CPU
+    /// register file replayed through the HVF layer.
+    pub fn restore_vm(state: &mut SystemState, snapshot: &VMSnapshot) -> Result<VMID> {
+        let cpu_states = match &snapshot.state {
+            VMState::Paused(cpus) | VMState::Trapped(_, cpus) => cpus.clone(),
+            _ => return Err(Error::InvalidVMState(snapshot.vmid)),
+        };
+
+        for region in &snapshot.regions {
+            crate::memory::MemoryManager::check_disjoint(state, snapshot.vmid, region)?;
+        }
+
+        let new_vmid = Self::create_vm(state, cpu_states.len() as u32)?;
+
+        if let Some(caps) = state.caps.get_mut(&new_vmid) {
+            caps.clear();
+        }
+        for cap in &snapshot.capabilities {
+            state.grant_capability(new_vmid, *cap);
+        }
+
+        if !snapshot.regions.is_empty() {
+            state.memory.insert(new_vmid, snapshot.regions.clone());
+        }
+
+        // Replay create_vm/initialize_vm to reach Runnable, then push the
+        // restored register files through the HVF layer, mirroring how a
+        // real restore reinitializes the hardware before resuming.
+        Self::initialize_vm(state, new_vmid, cpu_states.clone())?;
+        let ctx = HVF::create_vm(new_vmid)?;
+        let vcpus = HVF::create_vcpus(ctx, cpu_states.len() as u32)?;
+        for (vcpu, cpu_state) in vcpus.iter().zip(cpu_states.iter()) {
+            HVF::set_cpu_state(*vcpu, cpu_state)?;
+        }
+
+        // Reflect the snapshot's exact lifecycle state (Paused or
+        // Trapped) now that Runnable has been reached.
+        state.vms.insert(new_vmid, snapshot.state.clone());
+
+        Ok(new_vmid)
+    }
     
     /// Destroy VM and clean up resources
     ///
@@ -181,64 +433,309 @@ mod tests {
     #[test]
     fn test_vm_creation() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
-        
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
         assert!(state.vms.contains_key(&vmid));
         assert!(matches!(state.vms.get(&vmid), Some(VMState::Created)));
         assert!(state.has_capability(vmid, Capability::Execute));
     }
 
+    #[test]
+    fn test_create_vm_rejects_zero_vcpus() {
+        let mut state = SystemState::new();
+        assert!(VMManager::create_vm(&mut state, 0).is_err());
+    }
+
+    #[test]
+    fn test_create_vm_rejects_vcpu_count_above_max() {
+        let mut state = SystemState::new();
+        let max_vcpus = crate::hvf::HVF::get_system_info().unwrap().max_vcpus;
+        assert!(VMManager::create_vm(&mut state, max_vcpus + 1).is_err());
+    }
+
     #[test]
     fn test_vm_initialization() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
-        
-        let cpu_state = CPUState::default();
-        VMManager::initialize_vm(&mut state, vmid, cpu_state).unwrap();
-        
-        assert!(matches!(state.vms.get(&vmid), Some(VMState::Runnable(_))));
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+
+        assert!(matches!(state.vms.get(&vmid), Some(VMState::Runnable(cpus)) if cpus.len() == 1));
+    }
+
+    #[test]
+    fn test_initialize_vm_rejects_mismatched_vcpu_count() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 2).unwrap();
+
+        let result = VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_vm_trap_and_resume() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
-        
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
         let cpu_state = CPUState::default();
-        VMManager::initialize_vm(&mut state, vmid, cpu_state.clone()).unwrap();
-        
+        VMManager::initialize_vm(&mut state, vmid, vec![cpu_state.clone()]).unwrap();
+
         // Trap VM
         let exit_reason = crate::types::ExitReason::WFI;
-        VMManager::trap_vm(&mut state, vmid, exit_reason, cpu_state.clone()).unwrap();
-        
+        VMManager::trap_vm(&mut state, vmid, 0, exit_reason, cpu_state.clone()).unwrap();
+
         assert!(matches!(state.vms.get(&vmid), Some(VMState::Trapped(_, _))));
         assert_eq!(state.exits.len(), 1);
-        
+
         // Resume VM
-        VMManager::resume_vm(&mut state, vmid, cpu_state).unwrap();
+        VMManager::resume_vm(&mut state, vmid, 0, cpu_state).unwrap();
         assert!(matches!(state.vms.get(&vmid), Some(VMState::Runnable(_))));
     }
 
+    #[test]
+    fn test_smp_vm_traps_only_one_vcpu_at_a_time() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 2).unwrap();
+        VMManager::initialize_vm(
+            &mut state,
+            vmid,
+            vec![CPUState::default(), CPUState::default()],
+        )
+        .unwrap();
+
+        let trapped = CPUState {
+            pc: 0x4000,
+            ..Default::default()
+        };
+        VMManager::trap_vm(&mut state, vmid, 1, crate::types::ExitReason::WFI, trapped).unwrap();
+
+        match state.vms.get(&vmid) {
+            Some(VMState::Trapped(_, cpus)) => {
+                assert_eq!(cpus[0].pc, 0); // vCPU 0 untouched
+                assert_eq!(cpus[1].pc, 0x4000); // vCPU 1 trapped
+            }
+            other => panic!("expected Trapped, got {:?}", other),
+        }
+        assert_eq!(state.exits.front().unwrap().1, 1);
+    }
+
+    #[test]
+    fn test_trap_vm_rejects_out_of_range_vcpu() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+
+        let result = VMManager::trap_vm(
+            &mut state,
+            vmid,
+            1,
+            crate::types::ExitReason::WFI,
+            CPUState::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_trap_vm_reports_breakpoint_hit() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        state.breakpoints.entry(vmid).or_default().insert(0x4000);
+
+        let cpu_state = CPUState {
+            pc: 0x4000,
+            ..Default::default()
+        };
+        VMManager::trap_vm(&mut state, vmid, 0, crate::types::ExitReason::WFI, cpu_state).unwrap();
+
+        match state.vms.get(&vmid) {
+            Some(VMState::Trapped(reason, _)) => {
+                assert!(matches!(reason, crate::types::ExitReason::Breakpoint))
+            }
+            other => panic!("expected Trapped, got {:?}", other),
+        }
+        assert!(matches!(
+            state.exits.front().unwrap().2,
+            crate::types::ExitReason::Breakpoint
+        ));
+    }
+
+    #[test]
+    fn test_enter_debugging_from_trapped_and_resume() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(&mut state, vmid, Capability::Debug)
+            .unwrap();
+        let cpu_state = CPUState::default();
+        VMManager::initialize_vm(&mut state, vmid, vec![cpu_state.clone()]).unwrap();
+        VMManager::trap_vm(&mut state, vmid, 0, crate::types::ExitReason::WFI, cpu_state.clone())
+            .unwrap();
+
+        VMManager::enter_debugging(&mut state, vmid).unwrap();
+        assert!(matches!(state.vms.get(&vmid), Some(VMState::Debugging(_))));
+
+        VMManager::resume_vm(&mut state, vmid, 0, cpu_state).unwrap();
+        assert!(matches!(state.vms.get(&vmid), Some(VMState::Runnable(_))));
+    }
+
+    #[test]
+    fn test_enter_debugging_requires_debug_capability() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+        VMManager::trap_vm(
+            &mut state,
+            vmid,
+            0,
+            crate::types::ExitReason::WFI,
+            CPUState::default(),
+        )
+        .unwrap();
+
+        assert!(VMManager::enter_debugging(&mut state, vmid).is_err());
+    }
+
+    #[test]
+    fn test_configure_address_space_clamps_to_host_bits() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        let host_bits = HVF::get_system_info().unwrap().phys_addr_bits;
+
+        let effective =
+            VMManager::configure_address_space(&mut state, vmid, Some(host_bits + 10)).unwrap();
+
+        assert_eq!(effective, host_bits);
+        assert_eq!(state.addr_space_bits.get(&vmid), Some(&host_bits));
+    }
+
+    #[test]
+    fn test_configure_address_space_defaults_to_host_bits() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        let host_bits = HVF::get_system_info().unwrap().phys_addr_bits;
+
+        let effective = VMManager::configure_address_space(&mut state, vmid, None).unwrap();
+
+        assert_eq!(effective, host_bits);
+    }
+
+    #[test]
+    fn test_configure_address_space_requires_existing_vm() {
+        let mut state = SystemState::new();
+        let result = VMManager::configure_address_space(&mut state, VMID(99), Some(20));
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_vm_halt() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
-        
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
         VMManager::halt_vm(&mut state, vmid).unwrap();
         assert!(matches!(state.vms.get(&vmid), Some(VMState::Halted)));
     }
 
+    #[test]
+    fn test_pause_and_resume_from_pause() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            Capability::Snapshot,
+        )
+        .unwrap();
+
+        let cpu_state = CPUState::default();
+        VMManager::initialize_vm(&mut state, vmid, vec![cpu_state.clone()]).unwrap();
+
+        VMManager::pause_vm(&mut state, vmid).unwrap();
+        assert!(matches!(state.vms.get(&vmid), Some(VMState::Paused(_))));
+
+        VMManager::resume_from_pause(&mut state, vmid, vec![cpu_state]).unwrap();
+        assert!(matches!(state.vms.get(&vmid), Some(VMState::Runnable(_))));
+    }
+
+    #[test]
+    fn test_pause_requires_snapshot_capability() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+
+        assert!(VMManager::pause_vm(&mut state, vmid).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_vm_requires_paused_or_trapped() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            Capability::Snapshot,
+        )
+        .unwrap();
+        VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]).unwrap();
+
+        // Runnable is not snapshottable
+        assert!(VMManager::snapshot_vm(&state, vmid).is_err());
+
+        VMManager::pause_vm(&mut state, vmid).unwrap();
+        assert!(VMManager::snapshot_vm(&state, vmid).is_ok());
+    }
+
+    #[test]
+    fn test_snapshot_and_restore_vm_allocates_fresh_vmid() {
+        let mut state = SystemState::new();
+        let vmid = VMManager::create_vm(&mut state, 2).unwrap();
+        crate::capability::CapabilityManager::grant_capability(
+            &mut state,
+            vmid,
+            Capability::Snapshot,
+        )
+        .unwrap();
+        VMManager::initialize_vm(
+            &mut state,
+            vmid,
+            vec![CPUState::default(), CPUState::default()],
+        )
+        .unwrap();
+        VMManager::pause_vm(&mut state, vmid).unwrap();
+
+        let snapshot = VMManager::snapshot_vm(&state, vmid).unwrap();
+        let new_vmid = VMManager::restore_vm(&mut state, &snapshot).unwrap();
+
+        assert_ne!(new_vmid, vmid);
+        assert!(
+            matches!(state.vms.get(&new_vmid), Some(VMState::Paused(cpus)) if cpus.len() == 2)
+        );
+        assert!(state.has_capability(new_vmid, Capability::Snapshot));
+    }
+
+    #[test]
+    fn test_restore_vm_rejects_non_paused_trapped_snapshot() {
+        let snapshot = crate::migration::VMSnapshot {
+            vmid: VMID(0),
+            state: VMState::Created,
+            capabilities: Vec::new(),
+            regions: Vec::new(),
+            vcpu_count: 1,
+        };
+
+        let mut state = SystemState::new();
+        assert!(VMManager::restore_vm(&mut state, &snapshot).is_err());
+    }
+
     #[test]
     fn test_capability_enforcement() {
         let mut state = SystemState::new();
-        let vmid = VMManager::create_vm(&mut state).unwrap();
-        
+        let vmid = VMManager::create_vm(&mut state, 1).unwrap();
+
         // Remove Execute capability
         state.caps.get_mut(&vmid).unwrap().remove(&Capability::Execute);
-        
+
         // Should fail without capability
-        let cpu_state = CPUState::default();
-        let result = VMManager::initialize_vm(&mut state, vmid, cpu_state);
+        let result = VMManager::initialize_vm(&mut state, vmid, vec![CPUState::default()]);
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,164 @@
+//! Capability-gated inter-VM message channel
+//!
+//! Modeled on cloud-hypervisor's vsock device, but with transport fully
+//! replaced by the capability system: there is no shared mapping between
+//! VMs, only an explicit copy from the sender's guest memory into a
+//! bounded per-VM mailbox, so memory non-interference is never at risk.
+//! A send only succeeds if the sender holds `Capability::SendTo(target)`
+//! *and* the target holds a matching `Capability::ReceiveFrom(sender)`;
+//! otherwise callers should inject an undefined-instruction exception,
+//! exactly as for an unknown hypercall number.
+
+use std::collections::VecDeque;
+
+use crate::hvf::HVF;
+use crate::types::{Capability, SystemState, GPA, VMID};
+use crate::{Error, Result};
+
+/// Maximum number of bytes a VM's mailbox may hold at once
+pub const MAILBOX_CAPACITY: usize = 4096;
+
+/// Inter-VM vsock-style message channel
+pub struct Vsock;
+
+impl Vsock {
+    /// Copy `len` bytes from `sender`'s guest memory at `gpa` into
+    /// `target`'s mailbox
+    ///
+    /// Formal precondition: both VMs exist, `sender` holds
+    /// `SendTo(target)`, `target` holds `ReceiveFrom(sender)`, and the
+    /// copy fits within `target`'s remaining mailbox capacity.
+    /// Formal postcondition: `target`'s mailbox gains exactly `len` bytes.
+    pub fn send(state: &mut SystemState, sender: VMID, target: VMID, gpa: GPA, len: usize) -> Result<usize> {
+        if !state.vms.contains_key(&sender) {
+            return Err(Error::VMNotFound(sender));
+        }
+        if !state.vms.contains_key(&target) {
+            return Err(Error::VMNotFound(target));
+        }
+
+        if !state.has_capability(sender, Capability::SendTo(target)) {
+            return Err(Error::CapabilityError(format!(
+                "VM {sender} lacks SendTo({target}) capability"
+            )));
+        }
+        if !state.has_capability(target, Capability::ReceiveFrom(sender)) {
+            return Err(Error::CapabilityError(format!(
+                "VM {target} lacks ReceiveFrom({sender}) capability"
+            )));
+        }
+
+        let mailbox = state.mailboxes.entry(target).or_default();
+        if mailbox.len() + len > MAILBOX_CAPACITY {
+            return Err(Error::MemoryError(format!(
+                "mailbox for VM {target} has insufficient capacity for {len} bytes"
+            )));
+        }
+
+        let ctx = HVF::create_vm(sender)?;
+        let bytes = HVF::read_guest_memory(ctx, gpa.0, len)?;
+        mailbox.extend(bytes);
+
+        Ok(len)
+    }
+
+    /// Drain up to `max_len` bytes from `vmid`'s mailbox into guest memory
+    /// at `gpa`
+    ///
+    /// Formal precondition: VM exists.
+    /// Formal postcondition: the drained bytes are removed from the
+    /// mailbox; returns the number of bytes actually drained.
+    pub fn receive(state: &mut SystemState, vmid: VMID, gpa: GPA, max_len: usize) -> Result<usize> {
+        if !state.vms.contains_key(&vmid) {
+            return Err(Error::VMNotFound(vmid));
+        }
+
+        let drained: VecDeque<u8> = match state.mailboxes.get_mut(&vmid) {
+            Some(mailbox) => {
+                let n = max_len.min(mailbox.len());
+                mailbox.drain(..n).collect()
+            }
+            None => VecDeque::new(),
+        };
+        let bytes: Vec<u8> = drained.into_iter().collect();
+
+        let ctx = HVF::create_vm(vmid)?;
+        HVF::write_guest_memory(ctx, gpa.0, &bytes)?;
+
+        Ok(bytes.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::CapabilityManager;
+    use crate::vm::VMManager;
+
+    fn paired_vms() -> (SystemState, VMID, VMID) {
+        let mut state = SystemState::new();
+        let sender = VMManager::create_vm(&mut state, 1).unwrap();
+        let target = VMManager::create_vm(&mut state, 1).unwrap();
+        CapabilityManager::grant_capability(&mut state, sender, Capability::SendTo(target)).unwrap();
+        CapabilityManager::grant_capability(&mut state, target, Capability::ReceiveFrom(sender)).unwrap();
+        (state, sender, target)
+    }
+
+    #[test]
+    fn test_send_requires_send_to_capability() {
+        let mut state = SystemState::new();
+        let sender = VMManager::create_vm(&mut state, 1).unwrap();
+        let target = VMManager::create_vm(&mut state, 1).unwrap();
+        CapabilityManager::grant_capability(&mut state, target, Capability::ReceiveFrom(sender)).unwrap();
+
+        let result = Vsock::send(&mut state, sender, target, GPA(0x1000), 16);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_send_requires_matching_receive_from_capability() {
+        let mut state = SystemState::new();
+        let sender = VMManager::create_vm(&mut state, 1).unwrap();
+        let target = VMManager::create_vm(&mut state, 1).unwrap();
+        CapabilityManager::grant_capability(&mut state, sender, Capability::SendTo(target)).unwrap();
+
+        let result = Vsock::send(&mut state, sender, target, GPA(0x1000), 16);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_send_receive_round_trip() {
+        let (mut state, sender, target) = paired_vms();
+
+        let sent = Vsock::send(&mut state, sender, target, GPA(0x1000), 16).unwrap();
+        assert_eq!(sent, 16);
+
+        let received = Vsock::receive(&mut state, target, GPA(0x2000), 64).unwrap();
+        assert_eq!(received, 16);
+        assert!(state.mailboxes.get(&target).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mailbox_capacity_enforced() {
+        let (mut state, sender, target) = paired_vms();
+
+        Vsock::send(&mut state, sender, target, GPA(0x1000), MAILBOX_CAPACITY).unwrap();
+        let result = Vsock::send(&mut state, sender, target, GPA(0x1000), 1);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_send_is_deterministic() {
+        let (mut state_a, sender_a, target_a) = paired_vms();
+        let (mut state_b, sender_b, target_b) = paired_vms();
+
+        let result_a = Vsock::send(&mut state_a, sender_a, target_a, GPA(0x1000), 16);
+        let result_b = Vsock::send(&mut state_b, sender_b, target_b, GPA(0x1000), 16);
+
+        assert_eq!(result_a.unwrap(), result_b.unwrap());
+        assert_eq!(
+            state_a.mailboxes.get(&target_a).unwrap(),
+            state_b.mailboxes.get(&target_b).unwrap()
+        );
+    }
+}